@@ -0,0 +1,47 @@
+//! Plays a sequence of boards as short tweens, so cards glide to where they
+//! end up instead of jumping there.
+use cvsolitaire::Board;
+
+use std::time::{Duration, Instant};
+
+//How long each step takes, in milliseconds
+const STEP_MS: u64 = 150;
+
+pub struct Animation {
+    frames: Vec<Board>,
+    //Tweening from `frames[step]` to the one after it
+    step: usize,
+    since: Instant,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<Board>) -> Self {
+        Animation {
+            frames,
+            step: 0,
+            since: Instant::now(),
+        }
+    }
+
+    /// The two boards being tweened between and how far along the tween
+    /// is, from 0 to 1, or `None` once the last board has been reached.
+    pub fn frame(&mut self) -> Option<(&Board, &Board, f32)> {
+        let step = Duration::from_millis(STEP_MS);
+        while self.step + 1 < self.frames.len() {
+            let elapsed = self.since.elapsed();
+            if elapsed < step {
+                let ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+                let t = ms as f32 / STEP_MS as f32;
+                return Some((&self.frames[self.step], &self.frames[self.step + 1], ease(t)));
+            }
+            self.step += 1;
+            self.since += step;
+        }
+        None
+    }
+}
+
+//Starts and ends gently
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
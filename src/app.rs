@@ -0,0 +1,604 @@
+//! State shared by the window's event handlers, and the actions they trigger.
+use cvsolitaire::{board, Error, Game, GameStatus, Move, Result, Suit};
+use cvsolitaire::click::{self, Layout};
+use cvsolitaire::gamemove::{Stack, Target, Utility};
+use cvsolitaire::{save, settings, solver, stats};
+use cvsolitaire::settings::Settings;
+use cvsolitaire::stats::Stats;
+use orbtk::{Color, Image, Label, Point};
+use orbtk::traits::{Place, Text};
+use orbtk_simple_modal::Modal;
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use animation::Animation;
+use clock::{self, Clock};
+use graphics::{self, Surface};
+use pointer::Pointer;
+
+//How far the pointer has to move with the button down to start a drag
+const DRAG_SLOP: i32 = 4;
+//Longest gap between the clicks of a double-click, in milliseconds
+const DOUBLE_CLICK: u64 = 400;
+
+//How far the current game has made it into the statistics
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Tally {
+    Unplayed,
+    Playing,
+    Finished,
+}
+
+impl Tally {
+    fn of(game: &Game) -> Self {
+        if game.board().is_won() {
+            Tally::Finished
+        } else if game.history().is_empty() {
+            Tally::Unplayed
+        } else {
+            Tally::Playing
+        }
+    }
+}
+
+pub struct App {
+    pub game: RefCell<Game>,
+    //Prior click, waiting for a destination
+    pub last: RefCell<Option<Point>>,
+    picked_at: Cell<Option<Instant>>,
+    //Cards picked up from the keyboard, waiting for a destination
+    selected: Cell<Option<Target>>,
+    //Where the button went down, and where it is now once that's a drag
+    press: RefCell<Option<Point>>,
+    drag: RefCell<Option<Point>>,
+    pub hint: RefCell<Option<Move>>,
+    animation: RefCell<Option<Animation>>,
+    winnable_only: Cell<bool>,
+    clock: RefCell<Clock>,
+    stats: RefCell<Stats>,
+    tally: Cell<Tally>,
+    //Latest error or hint, shown after the game's numbers
+    message: RefCell<String>,
+    canvas: Arc<Image>,
+    layout: Cell<Layout>,
+    end_box: Arc<Modal>,
+    status: Arc<Label>,
+}
+
+impl App {
+    /// Starts the frontend on `game`, which has been played for `elapsed`.
+    pub fn new(game: Game, elapsed: Duration, canvas: Arc<Image>, end_box: Arc<Modal>,
+               status: Arc<Label>) -> Self {
+        App {
+            tally: Cell::new(Tally::of(&game)),
+            game: RefCell::new(game),
+            last: RefCell::new(None),
+            picked_at: Cell::new(None),
+            selected: Cell::new(None),
+            press: RefCell::new(None),
+            drag: RefCell::new(None),
+            hint: RefCell::new(None),
+            animation: RefCell::new(None),
+            winnable_only: Cell::new(load_settings().winnable_only),
+            clock: RefCell::new(Clock::start_at(elapsed)),
+            stats: RefCell::new(load_stats()),
+            message: RefCell::new(String::new()),
+            canvas,
+            layout: Cell::new(Layout::default()),
+            end_box,
+            status,
+        }
+    }
+
+    pub fn redraw(&self) {
+        let image = &mut *self.canvas.image.borrow_mut();
+        let canvas = &mut Surface::new(image, self.layout.get());
+        let game = self.game.borrow();
+        let press = *self.press.borrow();
+        let mut animation = self.animation.borrow_mut();
+        let tween = animation.as_mut().and_then(|a| a.frame());
+        match (tween, press, *self.drag.borrow()) {
+            (Some((from, to, t)), _, _) => graphics::render_tween(canvas, from, to, t),
+            (None, Some(from), Some(to)) => match self.lifted(from) {
+                Some(src) => graphics::render_lifted(canvas, game.board(), &src,
+                                                     Point::new(to.x - from.x, to.y - from.y)),
+                None => graphics::render(canvas, game.board()),
+            },
+            _ => graphics::render(canvas, game.board()),
+        }
+        if tween.is_none() {
+            *animation = None;
+        }
+        if let Some(ref m) = *self.hint.borrow() {
+            graphics::render_hint(canvas, game.board(), m);
+        }
+        if let Some(ref t) = self.selected.get() {
+            graphics::render_focus(canvas, game.board(), t);
+        }
+        graphics::render_cursor(canvas, self.last.borrow().as_ref());
+    }
+
+    /// Gives the canvas a new size, scaling the board to fit it.
+    pub fn resize(&self, width: u32, height: u32) {
+        *self.canvas.image.borrow_mut() =
+            ::orbimage::Image::from_color(width, height, Color::rgba(255, 255, 255, 0));
+        self.canvas.size(width, height);
+        self.layout.set(Layout::fit(width, height));
+        self.redraw();
+    }
+
+    pub fn set_status<S: Into<String>>(&self, text: S) {
+        *self.message.borrow_mut() = text.into();
+        self.show_status();
+    }
+
+    //Fills the status bar with the time, move count, deal and message
+    fn show_status(&self) {
+        let game = self.game.borrow();
+        let mut line = format!("{}   Moves: {}",
+                               clock::format(self.clock.borrow().elapsed()),
+                               game.history().len());
+        if let Some(seed) = game.board().seed {
+            line += &format!("   Deal #{}", seed);
+        }
+        let message = self.message.borrow();
+        if !message.is_empty() {
+            line += "   ";
+            line += &message;
+        }
+        if line != self.status.text.get() {
+            self.status.text(line);
+        }
+    }
+
+    /// Keeps the timer and any animation up to date. The timer stands
+    /// still while `paused`, e.g. when a modal covers the board, and once
+    /// the game is won.
+    pub fn tick(&self, paused: bool) {
+        if self.animation.borrow().is_some() {
+            self.redraw();
+        }
+        {
+            let mut clock = self.clock.borrow_mut();
+            if paused || self.game.borrow().board().is_won() {
+                clock.pause();
+            } else {
+                clock.resume();
+            }
+        }
+        self.show_status();
+    }
+
+    //Forgets anything tied to the previous position
+    fn reset(&self) {
+        *self.last.borrow_mut() = None;
+        self.selected.set(None);
+        *self.hint.borrow_mut() = None;
+        self.set_status("");
+        self.redraw();
+    }
+
+    pub fn new_game(&self, board: board::Board) {
+        //Walking away from a game counts against the streak
+        if self.tally.get() == Tally::Playing {
+            self.update_stats(|stats| stats.lose());
+        }
+        self.tally.set(Tally::Unplayed);
+        *self.animation.borrow_mut() = None;
+        *self.game.borrow_mut() = Game::new(board);
+        *self.clock.borrow_mut() = Clock::start();
+        self.reset();
+    }
+
+    pub fn winnable_only(&self) -> bool {
+        self.winnable_only.get()
+    }
+
+    /// Switches Winnable Deals Only, remembering the choice for next time.
+    pub fn set_winnable_only(&self, on: bool) {
+        self.winnable_only.set(on);
+        if let Some(path) = settings::default_path() {
+            if let Err(e) = settings::save(&path, &Settings { winnable_only: on }) {
+                eprintln!("Couldn't save settings to {}: {:?}", path.display(), e);
+            }
+        }
+    }
+
+    pub fn deal(&self) {
+        self.new_game(deal(self.winnable_only.get()));
+    }
+
+    //Plays the move from a click or drag at `src`, if any, to `dst`
+    fn play_between(&self, src: Option<Point>, dst: Point) -> Result<()> {
+        let layout = self.layout.get();
+        let src = src.and_then(|s| layout.hit(s.x, s.y));
+        let dst = layout.hit(dst.x, dst.y);
+        let mut game = self.game.borrow_mut();
+        click::to_move(&layout, game.board(), src.as_ref(), dst.as_ref())
+            .and_then(|m| game.play(m))
+    }
+
+    //The cards a press at `point` would pick up, if it can start a drag
+    fn lifted(&self, point: Point) -> Option<Target> {
+        let game = self.game.borrow();
+        let layout = self.layout.get();
+        layout.hit(point.x, point.y)
+            .and_then(|c| c.target(&layout, game.board(), true).ok())
+            .and_then(|t| match t {
+                Target::Stack { .. } | Target::Utility(_) => Some(t),
+                _ => None,
+            })
+    }
+
+    /// Handles the mouse, given where it is on the canvas.
+    pub fn pointer(&self, pointer: Pointer) {
+        self.selected.set(None);
+        let layout = self.layout.get();
+        let on_board = |p: Point| {
+            let (x, y) = layout.to_board(p.x, p.y);
+            Point::new(x, y)
+        };
+        match pointer {
+            Pointer::Press(point) => *self.press.borrow_mut() = Some(on_board(point)),
+            Pointer::Move(point) => self.drag_to(on_board(point)),
+            Pointer::Release(point) => self.release(on_board(point)),
+        }
+    }
+
+    //Starts or follows a drag, once the pointer has strayed far enough from
+    //a press on cards that can be picked up
+    fn drag_to(&self, point: Point) {
+        let press = match *self.press.borrow() {
+            Some(press) => press,
+            None => return,
+        };
+        if self.drag.borrow().is_none() {
+            let near = (point.x - press.x).abs() <= DRAG_SLOP &&
+                (point.y - press.y).abs() <= DRAG_SLOP;
+            if near || self.lifted(press).is_none() { return }
+        }
+        *self.last.borrow_mut() = None;
+        *self.drag.borrow_mut() = Some(point);
+        self.redraw();
+    }
+
+    //Drops dragged cards, or otherwise treats the press as a click
+    fn release(&self, point: Point) {
+        let press = self.press.borrow_mut().take();
+        let drag = self.drag.borrow_mut().take();
+        if drag.is_some() {
+            match self.play_between(press, point) {
+                Ok(()) => self.moved(true),
+                played => self.settle(played),
+            }
+        } else if self.is_double_click(point) {
+            self.auto(point);
+        } else {
+            self.click(point);
+        }
+    }
+
+    //Whether a click lands quickly on the spot the last click picked up
+    fn is_double_click(&self, point: Point) -> bool {
+        let quick = matches!(self.picked_at.get(),
+                             Some(at) if at.elapsed() < Duration::from_millis(DOUBLE_CLICK));
+        let near = matches!(*self.last.borrow(), Some(last)
+            if (point.x - last.x).abs() <= DRAG_SLOP && (point.y - last.y).abs() <= DRAG_SLOP);
+        quick && near
+    }
+
+    /// Sends the card at `point` to the ordered stacks, or else a free
+    /// utility slot.
+    pub fn auto(&self, point: Point) {
+        *self.last.borrow_mut() = None;
+        let played = {
+            let mut game = self.game.borrow_mut();
+            let layout = self.layout.get();
+            layout.hit(point.x, point.y).ok_or(Error::BadSourceOrDest)
+                .and_then(|c| c.target(&layout, game.board(), true))
+                .and_then(|t| Move::auto(game.board(), &t))
+                .and_then(|m| game.play(m))
+        };
+        self.settle(played);
+    }
+
+    //Follows up on a move attempted outside the two-click selection
+    fn settle(&self, played: Result<()>) {
+        match played {
+            Ok(()) => self.moved(false),
+            //Cards dropped back where they came from just go back
+            Err(Error::SameStack) => {
+                self.set_status("");
+                self.redraw();
+            },
+            Err(e) => {
+                self.set_status(e.to_string());
+                self.redraw();
+            },
+        }
+    }
+
+    pub fn click(&self, point: Point) {
+        let last = *self.last.borrow();
+        let dst = self.layout.get().hit(point.x, point.y);
+        let played = self.play_between(last, point);
+        match played {
+            Ok(()) => self.moved(false),
+            //A first click that isn't a move picks cards up
+            Err(_) if last.is_none() && !matches!(dst, Some(ref d) if d.is_button()) => {
+                *self.last.borrow_mut() = Some(point);
+                self.picked_at.set(Some(Instant::now()));
+                self.set_status("");
+                self.redraw();
+            },
+            //Clicking the picked-up stack again puts the cards back down
+            Err(Error::SameStack) => {
+                *self.last.borrow_mut() = None;
+                self.set_status("");
+                self.redraw();
+            },
+            Err(e) => {
+                *self.last.borrow_mut() = None;
+                self.set_status(e.to_string());
+                self.redraw();
+            },
+        }
+    }
+
+    //Follows up on a move that has just been played. Dragged cards are
+    //already where they were dropped, so only what came after is animated.
+    fn moved(&self, dragged: bool) {
+        //A game only counts as started once a move is made in it
+        if self.tally.get() == Tally::Unplayed {
+            self.tally.set(Tally::Playing);
+            self.update_stats(|stats| stats.start());
+        }
+        self.animate_last(if dragged { 1 } else { 0 });
+        self.reset();
+        self.check_status();
+    }
+
+    /// Keyboard counterpart to clicking a stack: picks up its top card,
+    /// takes one more card if it was already picked up, or drops the
+    /// cards picked up elsewhere on it.
+    pub fn select_stack(&self, stack: Stack) {
+        let len = self.game.borrow().board().in_play[stack as usize].len();
+        match self.selected.get() {
+            Some(Target::Stack { stack: s, idx }) if s == stack => {
+                //Past the bottom card, start over from the top
+                let idx = if idx == 0 { len - 1 } else { idx - 1 };
+                self.selected.set(Some(Target::Stack { stack, idx }));
+                self.redraw();
+            },
+            Some(_) => self.drop_selected(Target::Stack { stack, idx: len }),
+            None if len == 0 => self.refuse(Error::NoCardClicked),
+            None => self.pick(Target::Stack { stack, idx: len - 1 }),
+        }
+    }
+
+    /// Keyboard counterpart to clicking a utility slot.
+    pub fn select_utility(&self, u: Utility) {
+        match self.selected.get() {
+            Some(Target::Utility(s)) if s == u => self.cancel(),
+            Some(_) => self.drop_selected(Target::Utility(u)),
+            None if self.game.borrow().board().utility[u as usize].is_none() =>
+                self.refuse(Error::NothingInUtl),
+            None => self.pick(Target::Utility(u)),
+        }
+    }
+
+    /// Takes one card more or fewer from the stack picked up from the
+    /// keyboard.
+    pub fn select_deeper(&self, deeper: bool) {
+        if let Some(Target::Stack { stack, idx }) = self.selected.get() {
+            let len = self.game.borrow().board().in_play[stack as usize].len();
+            let idx = if deeper {
+                idx.saturating_sub(1)
+            } else {
+                ::std::cmp::min(idx + 1, len - 1)
+            };
+            self.selected.set(Some(Target::Stack { stack, idx }));
+            self.redraw();
+        }
+    }
+
+    /// Sends the cards picked up from the keyboard to the ordered stacks.
+    pub fn select_foundation(&self) {
+        let src = match self.selected.take() {
+            Some(src) => src,
+            None => return,
+        };
+        let played = {
+            let mut game = self.game.borrow_mut();
+            Move::to_foundation(game.board(), &src).and_then(|m| game.play(m))
+        };
+        self.settle(played);
+    }
+
+    pub fn collapse(&self, s: Suit) {
+        self.selected.set(None);
+        let played = self.game.borrow_mut().play(Move::CollapseJacks(s));
+        self.settle(played);
+    }
+
+    /// Drops whatever is picked up, by mouse or keyboard.
+    pub fn cancel(&self) {
+        *self.last.borrow_mut() = None;
+        self.selected.set(None);
+        self.redraw();
+    }
+
+    fn pick(&self, t: Target) {
+        *self.last.borrow_mut() = None;
+        self.selected.set(Some(t));
+        self.set_status("");
+        self.redraw();
+    }
+
+    fn drop_selected(&self, dst: Target) {
+        let src = match self.selected.take() {
+            Some(src) => src,
+            None => return,
+        };
+        let played = {
+            let mut game = self.game.borrow_mut();
+            Move::between(game.board(), Some(&src), Some(&dst)).and_then(|m| game.play(m))
+        };
+        self.settle(played);
+    }
+
+    fn refuse(&self, e: Error) {
+        self.selected.set(None);
+        self.settle(Err(e));
+    }
+
+    //Plays back the last turn, skipping its first `skip` boards
+    fn animate_last(&self, skip: usize) {
+        let frames = match self.game.borrow().history().last() {
+            Some(turn) => turn.frames().split_off(skip),
+            None => return,
+        };
+        *self.animation.borrow_mut() = Some(Animation::new(frames));
+    }
+
+    pub fn undo(&self) {
+        let after = self.game.borrow().board().clone();
+        if self.game.borrow_mut().undo() {
+            //Everything the turn did goes back in one step
+            let before = self.game.borrow().board().clone();
+            *self.animation.borrow_mut() = Some(Animation::new(vec![after, before]));
+            self.reset();
+        }
+    }
+
+    pub fn redo(&self) {
+        if self.game.borrow_mut().redo() {
+            self.animate_last(0);
+            self.reset();
+            self.check_status();
+        }
+    }
+
+    /// Writes the game in progress to the default save file.
+    pub fn save(&self) {
+        if let Some(path) = save::default_path() {
+            if let Err(e) = save::save(&path, &self.game.borrow(), self.clock.borrow().elapsed()) {
+                eprintln!("Couldn't save to {}: {:?}", path.display(), e);
+            }
+        }
+    }
+
+    /// Restores the game from the default save file, if there is one.
+    pub fn load(&self) {
+        if let Some((game, elapsed)) = load_saved() {
+            self.tally.set(Tally::of(&game));
+            *self.animation.borrow_mut() = None;
+            *self.game.borrow_mut() = game;
+            *self.clock.borrow_mut() = Clock::start_at(elapsed);
+            self.reset();
+        }
+    }
+
+    /// Highlights the first move of a short winning line, if the solver finds
+    /// one quickly.
+    pub fn hint(&self) {
+        *self.last.borrow_mut() = None;
+        let hint = solver::hint(self.game.borrow().board(), solver::HINT_NODE_BUDGET);
+        self.set_status(match hint {
+            Some(ref m) => format!("Hint: {}", m),
+            None => "No short winning line found from here.".to_string(),
+        });
+        *self.hint.borrow_mut() = hint;
+        self.redraw();
+    }
+
+    //Announces the end of the game, whether it was won or has run out of moves
+    fn check_status(&self) {
+        let game = self.game.borrow();
+        let text = match game.board().status() {
+            GameStatus::Won => {
+                if self.tally.get() == Tally::Playing {
+                    self.tally.set(Tally::Finished);
+                    let secs = self.clock.borrow().elapsed().as_secs();
+                    let moves = game.history().len();
+                    self.update_stats(|stats| stats.win(game.board().seed, secs, moves));
+                }
+                let won = match game.board().seed {
+                    Some(seed) => format!("You won deal #{}!", seed),
+                    None => "You won!".to_string(),
+                };
+                won + "\n\nChoose New Game from the menu to play again."
+            },
+            GameStatus::Stuck => "There are no moves left.\n\n\
+                                  Choose Undo from the menu to back up, \
+                                  or New Game to deal again.".to_string(),
+            GameStatus::InProgress => return,
+        };
+        self.end_box.text(text);
+        self.end_box.visible.set(true);
+    }
+
+    fn update_stats<F: FnOnce(&mut Stats)>(&self, f: F) {
+        let mut stats = self.stats.borrow_mut();
+        f(&mut stats);
+        if let Some(path) = stats::default_path() {
+            if let Err(e) = stats::save(&path, &stats) {
+                eprintln!("Couldn't save statistics to {}: {:?}", path.display(), e);
+            }
+        }
+    }
+
+    /// A summary of the statistics, for the Statistics modal.
+    pub fn stats_text(&self) -> String {
+        let stats = self.stats.borrow();
+        let mut text = format!("Games started: {}\n\
+                                Games won: {} ({}%)\n\
+                                Current streak: {}\n\
+                                Longest streak: {}\n",
+                               stats.started, stats.won, stats.win_percent(),
+                               stats.streak, stats.longest_streak);
+        if !stats.best.is_empty() {
+            text += "\nBest wins by deal:\n";
+            for (seed, best) in &stats.best {
+                text += &format!("#{}   fastest {}   fewest moves {}\n",
+                                 seed,
+                                 clock::format(Duration::from_secs(best.fastest_secs)),
+                                 best.fewest_moves);
+            }
+        }
+        text
+    }
+}
+
+/// A random deal, one the solver has proven winnable if `winnable_only`.
+pub fn deal(winnable_only: bool) -> board::Board {
+    if winnable_only {
+        board::Board::new_solvable(board::random_seed())
+    } else {
+        board::Board::new()
+    }
+}
+
+/// The settings from last time, or the defaults if there are none to read.
+pub fn load_settings() -> Settings {
+    settings::default_path()
+        .and_then(|path| settings::load(&path).ok())
+        .unwrap_or_default()
+}
+
+//The statistics so far, or a blank slate if there are none to read
+fn load_stats() -> Stats {
+    stats::default_path()
+        .and_then(|path| stats::load(&path).ok())
+        .unwrap_or_default()
+}
+
+/// The saved game and how long it has been played, unless there is none or
+/// it has already been won.
+pub fn load_saved() -> Option<(Game, Duration)> {
+    save::default_path()
+        .and_then(|path| save::load(&path).ok())
+        .filter(|saved| !saved.0.board().is_won())
+}
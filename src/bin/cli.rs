@@ -0,0 +1,90 @@
+//! A line-based frontend for terminals and CI boxes. Prints the board in
+//! board notation and reads moves in move notation from stdin.
+extern crate cvsolitaire;
+
+use cvsolitaire::{Board, Error, Game, GameStatus, Move};
+use cvsolitaire::record::Record;
+use cvsolitaire::solver;
+
+use std::env;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+Moves use move notation: stacks are 1-8, utility slots u1-u3 and ordered
+stacks f1-f3. For example 3-5, 3:2-5 (two cards), 3-u1, u1-f2, XR.
+Other commands:
+  board        print the board
+  undo, redo   take back or replay a move
+  hint         suggest a move that leads to a win
+  new [deal]   deal a new game, optionally by number
+  record       print the game record
+  help         show this message
+  quit         leave";
+
+fn new_game(deal: Option<&str>) -> Result<Game, String> {
+    match deal {
+        Some(deal) => deal.parse()
+            .map(|seed| Game::new(Board::from_seed(seed)))
+            .map_err(|_| format!("\"{}\" isn't a deal number.", deal)),
+        None => Ok(Game::new(Board::new())),
+    }
+}
+
+fn print_status(game: &Game) {
+    print!("\n{}", game.board());
+    match game.board().status() {
+        GameStatus::Won => println!("You won!"),
+        GameStatus::Stuck => println!("There are no moves left. Try undo or new."),
+        GameStatus::InProgress => (),
+    }
+}
+
+fn main() {
+    let mut game = match new_game(env::args().nth(1).as_deref()) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            ::std::process::exit(1);
+        },
+    };
+    print_status(&game);
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 { break }
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            None => (),
+            Some("quit") | Some("q") => break,
+            Some("help") | Some("?") => println!("{}", HELP),
+            Some("board") => print_status(&game),
+            Some("record") => print!("{}", Record::from_game(&game)),
+            Some("undo") => if game.undo() { print_status(&game) } else {
+                println!("Nothing to undo.")
+            },
+            Some("redo") => if game.redo() { print_status(&game) } else {
+                println!("Nothing to redo.")
+            },
+            Some("hint") => match solver::hint(game.board(), solver::HINT_NODE_BUDGET) {
+                Some(m) => println!("Try {}", m),
+                None => println!("No short winning line found from here."),
+            },
+            Some("new") => match new_game(words.next()) {
+                Ok(g) => {
+                    game = g;
+                    print_status(&game);
+                },
+                Err(e) => println!("{}", e),
+            },
+            Some(m) => match m.parse::<Move>().and_then(|m| game.play(m)) {
+                Ok(()) => print_status(&game),
+                Err(e @ Error::BadMove(_)) => println!("{} Type help for the notation.", e),
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! A full-screen terminal frontend. Arrow keys move a cursor over the
+//! utility slots, jack buttons, ordered stacks and stacks; Enter or Space
+//! picks cards up and drops them, just like clicking in the window.
+extern crate cvsolitaire;
+#[macro_use]
+extern crate crossterm;
+
+use cvsolitaire::{Board, Card, Game, GameStatus, Move, Suit};
+use cvsolitaire::board::CardOrJacks;
+use cvsolitaire::gamemove::{Stack, Utility, Ordered, Target};
+use cvsolitaire::solver;
+
+use crossterm::cursor;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetAttribute, SetBackgroundColor,
+                       SetForegroundColor, Attribute};
+use crossterm::terminal::{self, ClearType};
+
+use std::env;
+use std::io::{self, Write};
+
+//Columns are 6 characters wide; the top row holds the three utility slots,
+//the three jack buttons, the rose and the three ordered stacks
+const COL_WIDTH: u16 = 6;
+const TOP_SLOTS: usize = 9;
+const STACK_ROW: u16 = 4;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Cursor {
+    //0-2 utility, 3-5 jack buttons, 6-8 ordered stacks
+    Top(usize),
+    Stack(usize, usize),
+}
+
+struct Tui {
+    game: Game,
+    cursor: Cursor,
+    selected: Option<Target>,
+    message: String,
+}
+
+fn suit_color(s: Option<Suit>) -> Color {
+    match s {
+        Some(Suit::Red) => Color::DarkRed,
+        Some(Suit::Green) => Color::DarkGreen,
+        Some(Suit::Black) => Color::Black,
+        None => Color::DarkMagenta,
+    }
+}
+
+fn top_target(idx: usize) -> Target {
+    match idx {
+        0..=2 => Target::Utility(Utility::from_idx(idx)),
+        3 => Target::Jacks(Suit::Red),
+        4 => Target::Jacks(Suit::Green),
+        5 => Target::Jacks(Suit::Black),
+        _ => Target::Ordered(Ordered::from_idx(idx - 6)),
+    }
+}
+
+//Screen column of a top row slot; the rose sits between buttons and ordered
+fn top_col(idx: usize) -> u16 {
+    (if idx >= 6 { idx + 1 } else { idx }) as u16 * COL_WIDTH
+}
+
+impl Tui {
+    fn new(board: Board) -> Self {
+        let mut tui = Tui {
+            game: Game::new(board),
+            cursor: Cursor::Stack(0, usize::MAX),
+            selected: None,
+            message: String::new(),
+        };
+        tui.clamp();
+        tui
+    }
+
+    fn target(&self, cursor: Cursor) -> Target {
+        match cursor {
+            Cursor::Top(idx) => top_target(idx),
+            Cursor::Stack(col, row) => Target::Stack { stack: Stack::from_col(col), idx: row },
+        }
+    }
+
+    //Keeps the cursor on a card, or the bottom of an empty stack
+    fn clamp(&mut self) {
+        if let Cursor::Stack(col, row) = self.cursor {
+            let len = self.game.board().in_play[col].len();
+            self.cursor = Cursor::Stack(col, ::std::cmp::min(row, len.saturating_sub(1)));
+        }
+    }
+
+    //Moving onto a stack lands on its top card, the one most often picked
+    fn navigate(&mut self, code: KeyCode) {
+        let top = usize::MAX;
+        self.cursor = match (self.cursor, code) {
+            (Cursor::Top(idx), KeyCode::Left) => Cursor::Top((idx + TOP_SLOTS - 1) % TOP_SLOTS),
+            (Cursor::Top(idx), KeyCode::Right) => Cursor::Top((idx + 1) % TOP_SLOTS),
+            (Cursor::Top(idx), KeyCode::Down) => Cursor::Stack(::std::cmp::min(idx, 7), top),
+            (Cursor::Stack(col, _), KeyCode::Left) => Cursor::Stack((col + 7) % 8, top),
+            (Cursor::Stack(col, _), KeyCode::Right) => Cursor::Stack((col + 1) % 8, top),
+            (Cursor::Stack(col, 0), KeyCode::Up) => Cursor::Top(col),
+            (Cursor::Stack(col, row), KeyCode::Up) => Cursor::Stack(col, row - 1),
+            (Cursor::Stack(col, row), KeyCode::Down) => Cursor::Stack(col, row + 1),
+            (cursor, _) => cursor,
+        };
+        self.clamp();
+    }
+
+    //Picks up or drops at the cursor, the same way two clicks would
+    fn choose(&mut self) {
+        let dst = self.target(self.cursor);
+        let played = Move::between(self.game.board(), self.selected.as_ref(), Some(&dst))
+            .and_then(|m| self.game.play(m));
+        match played {
+            Ok(()) => {
+                self.selected = None;
+                self.message = match self.game.board().status() {
+                    GameStatus::Won => "You won! Press n for a new game.".to_string(),
+                    GameStatus::Stuck => "There are no moves left. Press u to undo.".to_string(),
+                    GameStatus::InProgress => String::new(),
+                };
+            },
+            Err(e) => {
+                if self.selected.is_none() {
+                    self.selected = Some(dst);
+                    self.message.clear();
+                } else {
+                    self.selected = None;
+                    self.message = e.to_string();
+                }
+            },
+        }
+        self.clamp();
+    }
+
+    fn hint(&mut self) {
+        self.message = match solver::hint(self.game.board(), solver::HINT_NODE_BUDGET) {
+            Some(m) => format!("Try {}", m),
+            None => "No short winning line found from here.".to_string(),
+        };
+    }
+
+    fn draw_card<W: Write>(&self, out: &mut W, text: String, suit: Option<Suit>,
+                           cursor: Cursor) -> io::Result<()> {
+        let target = self.target(cursor);
+        let selected = match (self.selected, target) {
+            (Some(Target::Stack { stack, idx }), Target::Stack { stack: s, idx: i }) =>
+                stack == s && i >= idx,
+            (selected, target) => selected == Some(target),
+        };
+        if selected { queue!(out, SetAttribute(Attribute::Reverse))?; }
+        if cursor == self.cursor { queue!(out, SetAttribute(Attribute::Underlined))?; }
+        queue!(out,
+               SetBackgroundColor(Color::White),
+               SetForegroundColor(suit_color(suit)),
+               Print(format!("{:^4}", text)),
+               SetAttribute(Attribute::Reset),
+               ResetColor)
+    }
+
+    fn draw<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let b = self.game.board();
+        queue!(out, terminal::Clear(ClearType::All))?;
+
+        for idx in 0..TOP_SLOTS {
+            queue!(out, cursor::MoveTo(top_col(idx), 1))?;
+            let (text, suit) = match top_target(idx) {
+                Target::Utility(u) => match b.utility[u as usize] {
+                    Some(ref c) => (c.to_string(), match *c {
+                        CardOrJacks::Card(ref c) => c.suit(),
+                        CardOrJacks::Jacks(ref j) => Some(j.suit()),
+                    }),
+                    None => ("..".to_string(), None),
+                },
+                Target::Jacks(s) => (format!("[{}]", match s {
+                    Suit::Red => 'R',
+                    Suit::Green => 'G',
+                    Suit::Black => 'B',
+                }), Some(s)),
+                Target::Ordered(o) => match b.ordered[o as usize].last() {
+                    Some(c) => (c.to_string(), Some(c.suit())),
+                    None => ("..".to_string(), None),
+                },
+                Target::Stack { .. } => unreachable!(),
+            };
+            self.draw_card(out, text, suit, Cursor::Top(idx))?;
+        }
+        queue!(out, cursor::MoveTo(6 * COL_WIDTH, 1))?;
+        let rose = b.joker.as_ref().map(Card::to_string).unwrap_or_else(|| "..".to_string());
+        queue!(out, Print(format!("{:^4}", rose)))?;
+
+        for (col, stack) in b.in_play.iter().enumerate() {
+            if stack.is_empty() {
+                queue!(out, cursor::MoveTo(col as u16 * COL_WIDTH, STACK_ROW))?;
+                self.draw_card(out, "..".to_string(), None, Cursor::Stack(col, 0))?;
+            }
+            for (row, card) in stack.iter().enumerate() {
+                queue!(out, cursor::MoveTo(col as u16 * COL_WIDTH, STACK_ROW + row as u16))?;
+                self.draw_card(out, card.to_string(), card.suit(), Cursor::Stack(col, row))?;
+            }
+        }
+
+        let bottom = STACK_ROW + b.in_play.iter().map(Vec::len).max().unwrap_or(0) as u16 + 2;
+        let deal = b.seed.map(|s| format!("Deal #{}  ", s)).unwrap_or_default();
+        queue!(out,
+               cursor::MoveTo(0, bottom),
+               Print(format!("{}Moves: {}", deal, self.game.history().len())),
+               cursor::MoveTo(0, bottom + 1),
+               Print(&self.message),
+               cursor::MoveTo(0, bottom + 3),
+               Print("arrows move  enter/space pick up & drop  esc cancel  \
+                      u undo  r redo  h hint  n new game  q quit"))?;
+        out.flush()
+    }
+}
+
+fn run<W: Write>(out: &mut W, tui: &mut Tui) -> io::Result<()> {
+    loop {
+        tui.draw(out)?;
+        let code = match event::read()? {
+            Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) => code,
+            _ => continue,
+        };
+        match code {
+            KeyCode::Char('q') => return Ok(()),
+            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => tui.navigate(code),
+            KeyCode::Enter | KeyCode::Char(' ') => tui.choose(),
+            KeyCode::Esc => tui.selected = None,
+            KeyCode::Char('u') => { tui.game.undo(); tui.selected = None; tui.clamp(); },
+            KeyCode::Char('r') => { tui.game.redo(); tui.selected = None; tui.clamp(); },
+            KeyCode::Char('h') => tui.hint(),
+            KeyCode::Char('n') => *tui = Tui::new(Board::new()),
+            _ => (),
+        }
+    }
+}
+
+fn main() {
+    let board = match env::args().nth(1) {
+        Some(deal) => match deal.parse() {
+            Ok(seed) => Board::from_seed(seed),
+            Err(_) => {
+                eprintln!("\"{}\" isn't a deal number.", deal);
+                ::std::process::exit(1);
+            },
+        },
+        None => Board::new(),
+    };
+    let mut tui = Tui::new(board);
+
+    let mut out = io::stdout();
+    terminal::enable_raw_mode().unwrap();
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide).unwrap();
+    let result = run(&mut out, &mut tui);
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen).unwrap();
+    terminal::disable_raw_mode().unwrap();
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
@@ -1,10 +1,13 @@
 use std::cmp::Ordering;
 use rand::{thread_rng, Rng};
+use ::rng::SplitMix64;
+use ::solver::{self, Solution};
 use ::gamemove::{Move, Valid};
 use ::error::Error::*;
 use ::Result;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum CardOrJacks {
     Card(Card),
     Jacks(Jacks),
@@ -28,7 +31,8 @@ impl CardOrJacks {
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Jacks {
     Red,
     Green,
@@ -44,16 +48,35 @@ impl Jacks {
         }
     }
 
+    pub fn suit(&self) -> Suit {
+        match *self {
+            Jacks::Red => Suit::Red,
+            Jacks::Green => Suit::Green,
+            Jacks::Black => Suit::Black,
+        }
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Suit {
     Red,
     Green,
     Black,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameStatus {
+    InProgress,
+    Won,
+    /// No legal moves remain, but the game hasn't been won.
+    Stuck,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub struct Board {
+    pub seed: Option<u64>,
     pub joker: Option<Card>,
     pub utility: [Option<CardOrJacks>; 3],
     pub ordered: [Vec<NumCard>; 3],
@@ -85,29 +108,27 @@ impl Board {
     fn validate_stack_can_parent(&self, dst: &::gamemove::Stack, c: &Card) -> Result<()> {
         if let Some(parent) = self.in_play[*dst as usize].last() {
             if !parent.can_parent(c) {
-                return Err(StackCantParent);
+                return Err(StackCantParent(c.clone()));
             }
         }
         Ok(())
     }
     
-    fn validate_idx_is_card(&self, src: &::gamemove::Stack, y: u32) -> Result<usize> {
-        (0..20u32)
-            .filter(|slot| y > (110 + (slot * 20)) && y < ((slot * 20) + 212))
-            .map(|idx| idx as usize)
-            .filter(|idx| *idx < self.in_play[*src as usize].len())
-            .max().ok_or(MustClickCard)
+    fn validate_depth(&self, src: &::gamemove::Stack, depth: usize) -> Result<usize> {
+        let len = self.in_play[*src as usize].len();
+        if depth == 0 { return Err(MustClickCard) }
+        if depth > len { return Err(NotEnoughCards) }
+        Ok(len - depth)
     }
     
     fn validate_stack_in_order(&self, src: &::gamemove::Stack, idx: usize) -> Result<()> {
-        if self.in_play[*src as usize].len() < 1 { return Err(StackOutOfOrder) }
+        if self.in_play[*src as usize].is_empty() { return Err(StackOutOfOrder) }
         if !in_order(&self.in_play[*src as usize][idx..]) { return Err(StackOutOfOrder) }
         Ok(())
     }
 
-    fn validate_stack_last(&self, src: &::gamemove::Stack, idx: usize) -> Result<&Card> {
-        if self.in_play[*src as usize].len() != idx + 1 { return Err(MultipleToSlot) }
-        Ok(self.in_play[*src as usize].last().unwrap())
+    fn validate_stack_last(&self, src: &::gamemove::Stack) -> Result<&Card> {
+        self.in_play[*src as usize].last().ok_or(NoCardClicked)
     }
 
     fn validate_card_num<'a>(&self, src: &'a Card) -> Result<&'a NumCard> {
@@ -125,66 +146,52 @@ impl Board {
 
     fn validate_ord_can_parent(&self, src: &NumCard, dst: &::gamemove::Ordered) -> Result<()> {
         if let Some(dst) = self.ordered[*dst as usize].last() {
-            if !dst.can_parent_ord(src) { return Err(OrdCantParent) }
-        } else if src.value() != 0 { return Err(OrdCantParent) }
+            if !dst.can_parent_ord(src) { return Err(OrdCantParent(src.clone())) }
+        } else if src.value() != 0 { return Err(OrdCantParent(src.clone())) }
         Ok(())
     }
     
     pub fn get_valid(&self, m: Move) -> Result<Valid> {
-        use ::gamemove::{Move, StackPosition};
-        use ::gamemove::ClickTarget::*;
-        
         match m {
-            Move {dst: Some(RedButton), ..} =>
-                self.validate_jack(Suit::Red)?,
-            Move {dst: Some(GreenButton), ..} =>
-                self.validate_jack(Suit::Green)?,
-            Move {dst: Some(BlackButton), ..} =>
-                self.validate_jack(Suit::Black)?,
-            Move{src: Some(Utility(ref src)),
-                 dst: Some(Stack(StackPosition{stack: ref dst, ..}))} => {
+            Move::CollapseJacks(s) => self.validate_jack(s)?,
+            Move::UtilityToStack { ref src, ref dst } => {
                 let c = self.validate_utility_is_card(src)?;
                 self.validate_stack_can_parent(dst, c)?;
             },
-            Move{src: Some(Stack(StackPosition{stack: ref src, y})),
-                 dst: Some(Stack(StackPosition{stack: ref dst, ..}))} => {
-                //Ensure the first click is on a card
-                let idx = self.validate_idx_is_card(src, y)?;
-                //Make sure everything below the clicked card is in order
+            Move::StackToStack { ref src, depth, ref dst } => {
+                //Putting cards back where they are isn't a move
+                if src == dst { return Err(SameStack) }
+                //Ensure the move picks up at least one card
+                let idx = self.validate_depth(src, depth)?;
+                //Make sure everything below the picked card is in order
                 self.validate_stack_in_order(src, idx)?;
                 //TODO: This is probably redunant now...
                 let c = self.validate_src_exists(src, idx)?;
                 //Ensure destination can take the source
                 self.validate_stack_can_parent(dst, c)?;
             },
-            Move{src: Some(Utility(ref src)),
-                 dst: Some(Ordered(ref dst))} => {
-                //Ensure the first click is on a card
+            Move::UtilityToFoundation { ref src, ref dst } => {
+                //Ensure the utility slot holds a card
                 let c = self.validate_utility_is_card(src)?;
                 //Ensure it's a number card; Jacks can't move to ordered stack
                 let num = self.validate_card_num(c)?;
                 //Ensure this is the next card in the ordered stack
                 self.validate_ord_can_parent(num, dst)?;
                 },
-            Move{src: Some(Stack(StackPosition{stack: ref src, y})),
-                 dst: Some(Ordered(ref dst))} => {
-                let idx = self.validate_idx_is_card(src, y)?;
+            Move::StackToFoundation { ref src, ref dst } => {
                 //Only the last card in stack can move to ordered
-                let c = self.validate_stack_last(src, idx)?;
+                let c = self.validate_stack_last(src)?;
                 //Ensure it's a number card; Jacks can't move to ordered stack
                 let num = self.validate_card_num(c)?;
                 //Ensure this is the next card in the ordered stack
                 self.validate_ord_can_parent(num, dst)?;
             },
-            Move{src: Some(Stack(StackPosition{stack: ref src, y})),
-                 dst: Some(Utility(ref dst))} => {
-                let idx = self.validate_idx_is_card(src, y)?;
-                //Ensure source is the last card
-                self.validate_stack_last(src, idx)?;
+            Move::StackToUtility { ref src, ref dst } => {
+                //Ensure there is a card to move
+                self.validate_stack_last(src)?;
                 //Ensure destination slot is open
                 self.validate_utility_open(dst)?;
             }
-            _ => return Err(BadSourceOrDest),
         };
         Ok(Valid(m))
     }
@@ -213,7 +220,7 @@ impl Board {
             stack.retain(|card| !(card.is_jack() && card.suit() == Some(s)));
         }
         //Add jack bundle to first clear utility
-        if let Some(card) = self.utility.iter_mut().find(|c|**c == None) {
+        if let Some(card) = self.utility.iter_mut().find(|c| c.is_none()) {
             *card = Some(Jacks(Jacks::from_suit(s)));
         }             
     }
@@ -221,19 +228,13 @@ impl Board {
     //Chnages the underlying board data to execute a validated move.
     //Unwrap calls are ok here, because the mvoe has been validated.
     pub fn make_move(&mut self, Valid(m): Valid) {
-        use ::gamemove::{Move, StackPosition};
-        use ::gamemove::ClickTarget::*;
         use self::CardOrJacks::*;
         match m {
-            Move{dst: Some(RedButton), ..} => self.clear_jacks(Suit::Red),
-            Move{dst: Some(GreenButton), ..} => self.clear_jacks(Suit::Green),
-            Move{dst: Some(BlackButton), ..} => self.clear_jacks(Suit::Black),
-            Move{src: Some(Utility(src)),
-                 dst: Some(Stack(StackPosition{stack: dst, ..}))} =>
+            Move::CollapseJacks(s) => self.clear_jacks(s),
+            Move::UtilityToStack { src, dst } =>
                 self.in_play[dst as usize]
                     .push(self.utility[src as usize].take().unwrap().into_card().unwrap()),
-            Move{src: Some(Utility(src)),
-                 dst: Some(Ordered(dst))} => {
+            Move::UtilityToFoundation { src, dst } => {
                 let tmp = self.utility[src as usize]
                     .take()
                     .unwrap()
@@ -243,34 +244,52 @@ impl Board {
                     .unwrap();
                 self.ordered[dst as usize].push(tmp);
                 },
-            Move{src: Some(Stack(StackPosition{stack:src, ..})),
-                 dst: Some(Utility(dst)) } => self.utility[dst as usize] =
+            Move::StackToUtility { src, dst } => self.utility[dst as usize] =
                 Some(Card(self.in_play[src as usize].pop().unwrap())),
-            Move{src: Some(Stack(StackPosition{stack: src, y})),
-                 dst: Some(Stack(StackPosition{stack: dst, ..})) } => {
-                let idx = self.validate_idx_is_card(&src, y).unwrap();
+            Move::StackToStack { src, depth, dst } => {
+                let idx = self.in_play[src as usize].len() - depth;
                 let tmp: Vec<_> = self.in_play[src as usize].drain(idx..).collect();
                 for card in tmp {
                     self.in_play[dst as usize].push(card);
                 }
             },
-            Move{src: Some(Stack(StackPosition{stack: src, ..})),
-                 dst: Some(Ordered(dst))} => { 
+            Move::StackToFoundation { src, dst } => { 
                 let tmp = self.in_play[src as usize].pop()
                     .unwrap()
                     .into_num()
                     .unwrap();
                 self.ordered[dst as usize].push(tmp);
             },
-            _ => panic!("Invalid move passed as valid!"), 
         }
     }
 
+    /// Deals a new game with a random deal number.
     pub fn new() -> Self {
+        Board::from_seed(random_seed())
+    }
+
+    /// Deals the first game at or after `seed` that the solver can prove is
+    /// winnable within `solver::DEAL_NODE_BUDGET`. The returned board's seed
+    /// is the deal that was accepted, so `Board::from_seed` reproduces it.
+    pub fn new_solvable(seed: u64) -> Self {
+        let mut seed = seed;
+        loop {
+            let board = Board::from_seed(seed);
+            if let Solution::Solved(_) = solver::solve(&board, solver::DEAL_NODE_BUDGET) {
+                return board;
+            }
+            seed = seed.wrapping_add(1);
+        }
+    }
+
+    /// Deals the game identified by `seed`. The same seed always produces
+    /// the same deal.
+    pub fn from_seed(seed: u64) -> Self {
         use self::Card::*;
         use self::NumCard::*;
           
         let mut board = Board {
+            seed: Some(seed),
             joker: None,
             utility: [None, None, None],
             ordered: [Vec::new(), Vec::new(), Vec::new()],
@@ -300,8 +319,7 @@ impl Board {
             deck.push(Num(Green(i)));
         }
 
-        let mut rng = thread_rng();
-        rng.shuffle(&mut deck);
+        SplitMix64::new(seed).shuffle(&mut deck);
 
         for stack in &mut board.in_play {
             for _ in 0..5 { stack.push(deck.pop().unwrap()); }            
@@ -311,12 +329,63 @@ impl Board {
         board
     }
 
+    /// The game is won once every stack is empty and the utility slots hold
+    /// nothing but collapsed jacks.
+    pub fn is_won(&self) -> bool {
+        self.in_play.iter().all(Vec::is_empty) &&
+            !self.utility.iter().any(|slot| matches!(*slot, Some(CardOrJacks::Card(_))))
+    }
+
+    pub fn status(&self) -> GameStatus {
+        if self.is_won() {
+            GameStatus::Won
+        } else if self.legal_moves().is_empty() {
+            GameStatus::Stuck
+        } else {
+            GameStatus::InProgress
+        }
+    }
+
+    /// Every move `get_valid` would accept from this position.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        use ::gamemove::{Stack, Utility, Ordered};
+
+        let mut candidates = vec![
+            Move::CollapseJacks(Suit::Red),
+            Move::CollapseJacks(Suit::Green),
+            Move::CollapseJacks(Suit::Black),
+        ];
+        for src in (0..8).map(Stack::from_col) {
+            for depth in 1..self.in_play[src as usize].len() + 1 {
+                for dst in (0..8).map(Stack::from_col).filter(|dst| *dst != src) {
+                    candidates.push(Move::StackToStack { src, depth, dst });
+                }
+            }
+            for dst in (0..3).map(Utility::from_idx) {
+                candidates.push(Move::StackToUtility { src, dst });
+            }
+            for dst in (0..3).map(Ordered::from_idx) {
+                candidates.push(Move::StackToFoundation { src, dst });
+            }
+        }
+        for src in (0..3).map(Utility::from_idx) {
+            for dst in (0..8).map(Stack::from_col) {
+                candidates.push(Move::UtilityToStack { src, dst });
+            }
+            for dst in (0..3).map(Ordered::from_idx) {
+                candidates.push(Move::UtilityToFoundation { src, dst });
+            }
+        }
+        candidates.into_iter()
+            .filter(|m| self.get_valid(m.clone()).is_ok())
+            .collect()
+    }
 
     fn autosweep(&mut self) -> Option<Card> {
         use self::Card::*;
         //Calculate the minimum card in the stacks or utility slots
         let min_in_play = self.in_play.iter()
-            .flat_map(|v| v)
+            .flatten()
             .chain(self.utility.iter()
                    .filter_map(Option::as_ref)
                    .filter_map(|c| c.card().ok()))                
@@ -344,10 +413,21 @@ impl Board {
         None
     }
     
-    pub fn sweep_free(&mut self) {
-        while let Some(card) = self.autosweep() {
-            self.insert_ordered(card);
+    /// Moves every card that can no longer be useful to the ordered stacks
+    /// (or the rose, for the joker). Returns the moved cards in order.
+    pub fn sweep_free(&mut self) -> Vec<Card> {
+        let mut swept = Vec::new();
+        while let Some(card) = self.sweep_one() {
+            swept.push(card);
         }
+        swept
+    }
+
+    /// Takes a single step of `sweep_free`, returning the card it moved.
+    pub fn sweep_one(&mut self) -> Option<Card> {
+        let card = self.autosweep()?;
+        self.insert_ordered(card.clone());
+        Some(card)
     }
 
     fn insert_ordered(&mut self, card: Card) {
@@ -364,7 +444,14 @@ impl Board {
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Card {
     JBlack,
     JRed,
@@ -373,7 +460,8 @@ pub enum Card {
     Num(NumCard),
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum NumCard {
     Black(usize),
     Red(usize),
@@ -383,7 +471,7 @@ pub enum NumCard {
 impl Card {
     fn can_parent(&self, other: &Card) -> bool {
         use self::Card::*;
-        if let (&Num(ref my_nc), &Num(ref other_nc)) = (self, other) {
+        if let (Num(my_nc), Num(other_nc)) = (self, other) {
             my_nc.suit() != other_nc.suit() && my_nc.value() == other_nc.value() + 1
         } else { false }
     }
@@ -406,16 +494,11 @@ impl Card {
         self.num().map(|n|n.value())
     }
 
-    fn is_jack(&self) -> bool {
-        match *self {
-            Card::JRed |
-            Card::JGreen |
-            Card::JBlack => true,
-            _=> false
-        }
+    pub fn is_jack(&self) -> bool {
+        matches!(*self, Card::JRed | Card::JGreen | Card::JBlack)
     }
     
-    fn suit(&self) -> Option<Suit> {
+    pub fn suit(&self) -> Option<Suit> {
         match *self {
             Card::Num(ref nc) => Some(nc.suit()),
             Card::JRed => Some(Suit::Red),
@@ -427,7 +510,7 @@ impl Card {
 }
 
 impl NumCard {
-    fn suit(&self) -> Suit {
+    pub fn suit(&self) -> Suit {
         match *self {
             NumCard::Red(_) => Suit::Red,
             NumCard::Green(_) => Suit::Green,
@@ -460,7 +543,41 @@ impl PartialOrd for NumCard {
     }
 }
 
+/// Picks a deal number at random.
+pub fn random_seed() -> u64 {
+    thread_rng().gen()
+}
+
 fn in_order(cards: &[Card]) -> bool {
-    if cards.len() < 1 { return true }
+    if cards.is_empty() { return true }
     !cards.windows(2).any(|s|!s[0].can_parent(&s[1]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Card::*;
+    use super::NumCard::*;
+
+    //Changing the deck order or the shuffle renumbers every deal
+    #[test]
+    fn seed_42_deals_the_same_layout() {
+        let b = Board::from_seed(42);
+        assert_eq!(b.in_play[0], vec![Num(Black(0)), JBlack, JBlack, JGreen, Num(Black(7))]);
+        assert_eq!(b.in_play[4], vec![JGreen, Num(Red(2)), Num(Green(6))]);
+        assert_eq!(b.in_play[7],
+                   vec![Num(Green(1)), JRed, Num(Red(7)), Num(Black(1)), Num(Black(4))]);
+        assert_eq!(b.joker, Some(Joker));
+        assert_eq!(b.seed, Some(42));
+    }
+
+    #[test]
+    fn stack_onto_itself_is_refused() {
+        use ::gamemove::Stack::*;
+        let b = Board::from_seed(42);
+        for &(stack, depth) in &[(Stack2, 1), (Stack7, 1), (Stack7, 2)] {
+            let m = Move::StackToStack { src: stack, depth, dst: stack };
+            assert!(matches!(b.get_valid(m), Err(SameStack)));
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Translates clicks on the canvas into logical moves. This is the only part
+//! of the library that knows where things are drawn.
+use ::board::{Board, Suit};
+use ::gamemove::{Move, Stack, Utility, Ordered, Target};
+use ::error::Error::*;
+use ::Result;
+
+static STACKS: (i32, i32) = (0, 110);
+static UTILITIES: (i32, i32) = (0, 0);
+static BUTTONS: (i32, i32) = (245, 0);
+static ROSE: (i32, i32) = (300, 0);
+static ORDERED: (i32, i32) = (375, 0);
+//Distance between neighbouring slots or stacks, and between the tops of
+//cards fanned out in a stack
+static PITCH: i32 = 75;
+static FAN: i32 = 20;
+//The closest cards get fanned when a tall stack is squeezed to fit
+static MIN_FAN: i32 = 6;
+static BUTTON_PITCH: i32 = 35;
+//Smallest the board is drawn at. A canvas too small for it clips the board
+//instead of shrinking it to nothing.
+static MIN_SCALE: f32 = 0.1;
+
+/// The size of a card.
+pub static CARD: (u32, u32) = (70, 102);
+pub static BUTTON: (u32, u32) = (30, 30);
+/// The size of the board in board coordinates, which is also the size it
+/// is drawn at when not scaled.
+pub static BOARD_SIZE: (u32, u32) = (595, 430);
+
+/// A rectangle in board coordinates.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Area {
+    pub fn new(x: i32, y: i32, w: u32, h: u32) -> Area {
+        Area { x, y, w, h }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.w as i32 && y >= self.y && y < self.y + self.h as i32
+    }
+}
+
+/// Where everything on the board goes. Drawing and hit-testing both ask
+/// the layout, so what is clicked is always what is drawn there.
+///
+/// Positions are in board coordinates. The layout also maps those onto a
+/// canvas of any size: the board keeps its proportions, filling the canvas
+/// as far as it can while centered across it. `height` is how much of the
+/// board fits on the canvas, which can be more than `BOARD_SIZE` when the
+/// canvas is relatively tall.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Layout {
+    pub scale: f32,
+    pub origin: (i32, i32),
+    pub height: u32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout { scale: 1.0, origin: (0, 0), height: BOARD_SIZE.1 }
+    }
+}
+
+impl Layout {
+    pub fn fit(width: u32, height: u32) -> Layout {
+        let scale = (width as f32 / BOARD_SIZE.0 as f32)
+            .min(height as f32 / BOARD_SIZE.1 as f32)
+            .max(MIN_SCALE);
+        let used = (BOARD_SIZE.0 as f32 * scale).round() as i32;
+        Layout {
+            scale,
+            origin: ((width as i32 - used) / 2, 0),
+            height: (height as f32 / scale) as u32,
+        }
+    }
+
+    pub fn to_canvas(&self, x: i32, y: i32) -> (i32, i32) {
+        (self.origin.0 + (x as f32 * self.scale).round() as i32,
+         self.origin.1 + (y as f32 * self.scale).round() as i32)
+    }
+
+    /// The board coordinates of a point on the canvas, for hit-testing.
+    pub fn to_board(&self, x: i32, y: i32) -> (i32, i32) {
+        (((x - self.origin.0) as f32 / self.scale).floor() as i32,
+         ((y - self.origin.1) as f32 / self.scale).floor() as i32)
+    }
+
+    pub fn utility(&self, u: Utility) -> Area {
+        Area::new(UTILITIES.0 + u as i32 * PITCH, UTILITIES.1, CARD.0, CARD.1)
+    }
+
+    pub fn ordered(&self, o: Ordered) -> Area {
+        Area::new(ORDERED.0 + o as i32 * PITCH, ORDERED.1, CARD.0, CARD.1)
+    }
+
+    pub fn rose(&self) -> Area {
+        Area::new(ROSE.0, ROSE.1, CARD.0, CARD.1)
+    }
+
+    pub fn button(&self, s: Suit) -> Area {
+        let row = match s {
+            Suit::Red => 0,
+            Suit::Green => 1,
+            Suit::Black => 2,
+        };
+        Area::new(BUTTONS.0, BUTTONS.1 + row * BUTTON_PITCH, BUTTON.0, BUTTON.1)
+    }
+
+    //The column a stack's cards are drawn in, from its first card down
+    fn column(&self, stack: Stack) -> Area {
+        let x = STACKS.0 + stack as i32 * PITCH;
+        Area::new(x, STACKS.1, PITCH as u32, (self.height as i32 - STACKS.1) as u32)
+    }
+
+    /// How far apart the cards of a stack are fanned out. Stacks that would
+    /// run off the bottom of the canvas are squeezed together to fit, down
+    /// to a point where each card still shows enough to be clicked.
+    pub fn fan(&self, b: &Board, stack: Stack) -> i32 {
+        let len = b.in_play[stack as usize].len() as i32;
+        if len < 2 { return FAN }
+        let room = self.height as i32 - STACKS.1 - CARD.1 as i32;
+        ::std::cmp::min(FAN, ::std::cmp::max(MIN_FAN, room / (len - 1)))
+    }
+
+    /// Card `idx` of a stack, or where it would go if there is no such card.
+    pub fn card(&self, b: &Board, stack: Stack, idx: usize) -> Area {
+        let column = self.column(stack);
+        Area::new(column.x, column.y + idx as i32 * self.fan(b, stack), CARD.0, CARD.1)
+    }
+
+    /// The cards of a stack from `idx` to the top, or its empty slot.
+    pub fn run(&self, b: &Board, stack: Stack, idx: usize) -> Area {
+        let len = b.in_play[stack as usize].len();
+        let idx = if len == 0 { 0 } else { ::std::cmp::min(idx, len - 1) };
+        let first = self.card(b, stack, idx);
+        let last = self.card(b, stack, ::std::cmp::max(len, 1) - 1);
+        Area::new(first.x, first.y, CARD.0, (last.y + last.h as i32 - first.y) as u32)
+    }
+
+    /// What lies under a point, in board coordinates.
+    pub fn hit(&self, x: i32, y: i32) -> Option<ClickTarget> {
+        use ::gamemove::Utility::*;
+        use ::gamemove::Ordered::*;
+        let utilities = [Utility0, Utility1, Utility2];
+        let ordered = [Ordered0, Ordered1, Ordered2];
+        let buttons = [(Suit::Red, ClickTarget::RedButton),
+                       (Suit::Green, ClickTarget::GreenButton),
+                       (Suit::Black, ClickTarget::BlackButton)];
+        if let Some(u) = utilities.iter().find(|u| self.utility(**u).contains(x, y)) {
+            return Some(ClickTarget::Utility(*u));
+        }
+        if let Some(o) = ordered.iter().find(|o| self.ordered(**o).contains(x, y)) {
+            return Some(ClickTarget::Ordered(*o));
+        }
+        if let Some((_, b)) = buttons.iter().find(|&&(s, _)| self.button(s).contains(x, y)) {
+            return Some(b.clone());
+        }
+        //Anywhere in a stack's column counts as that stack
+        (0..8).map(Stack::from_col)
+            .find(|s| self.column(*s).contains(x, y))
+            .map(|s| ClickTarget::Stack(StackPosition { stack: s, y: y as u32 }))
+    }
+
+    /// The card of a stack under a point `y` down the board, favoring the
+    /// card on top.
+    pub fn card_at(&self, b: &Board, stack: Stack, y: i32) -> Option<usize> {
+        let x = self.column(stack).x;
+        (0..b.in_play[stack as usize].len()).rev()
+            .find(|idx| self.card(b, stack, *idx).contains(x, y))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackPosition {
+    pub stack: Stack,
+    pub y: u32, 
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ClickTarget {
+    Stack(StackPosition),
+    Utility(Utility),
+    Ordered(Ordered),
+    GreenButton,
+    RedButton,
+    BlackButton,
+}
+
+impl ClickTarget {
+    pub fn is_button(&self) -> bool {
+        matches!(*self, ClickTarget::RedButton | ClickTarget::GreenButton | ClickTarget::BlackButton)
+    }
+
+    /// The board target under a click. A click picking cards up must land
+    /// on a card; one dropping them anywhere on a stack means its top.
+    pub fn target(&self, layout: &Layout, b: &Board, picking: bool) -> Result<Target> {
+        use self::ClickTarget::*;
+        Ok(match *self {
+            Stack(StackPosition{stack, y}) => Target::Stack {
+                stack,
+                idx: if picking {
+                    layout.card_at(b, stack, y as i32).ok_or(MustClickCard)?
+                } else {
+                    b.in_play[stack as usize].len()
+                },
+            },
+            Utility(u) => Target::Utility(u),
+            Ordered(o) => Target::Ordered(o),
+            RedButton => Target::Jacks(Suit::Red),
+            GreenButton => Target::Jacks(Suit::Green),
+            BlackButton => Target::Jacks(Suit::Black),
+        })
+    }
+}
+
+/// Resolves a pair of clicks into the logical move they describe. The move
+/// still has to be checked with `Board::get_valid`.
+pub fn to_move(layout: &Layout, b: &Board, src: Option<&ClickTarget>, dst: Option<&ClickTarget>)
+               -> Result<Move> {
+    let dst = match dst {
+        Some(dst) => Some(dst.target(layout, b, false)?),
+        None => None,
+    };
+    //The jack buttons work whatever was clicked before them
+    let src = match (src, dst) {
+        (_, Some(Target::Jacks(_))) | (None, _) => None,
+        (Some(src), _) => Some(src.target(layout, b, true)?),
+    };
+    Move::between(b, src.as_ref(), dst.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::board::Card;
+    use ::gamemove::Utility::*;
+
+    #[test]
+    fn empty_canvas_still_has_a_scale() {
+        let layout = Layout::fit(0, 0);
+        assert_eq!(layout.scale, MIN_SCALE);
+        assert_eq!(layout.height, 0);
+        assert_eq!(layout.to_board(layout.origin.0, layout.origin.1), (0, 0));
+    }
+
+    #[test]
+    fn hit_finds_slots_and_stacks() {
+        let layout = Layout::default();
+        assert_eq!(layout.hit(10, 10), Some(ClickTarget::Utility(Utility0)));
+        assert_eq!(layout.hit(BUTTONS.0 + 5, BUTTONS.1 + 5), Some(ClickTarget::RedButton));
+        assert_eq!(layout.hit(80, STACKS.1),
+                   Some(ClickTarget::Stack(StackPosition { stack: Stack::from_col(1),
+                                                           y: STACKS.1 as u32 })));
+    }
+
+    #[test]
+    fn hit_misses_above_the_stacks() {
+        let layout = Layout::default();
+        //The rose, and the gap between the utility slots and the buttons
+        assert_eq!(layout.hit(320, 50), None);
+        assert_eq!(layout.hit(230, 50), None);
+        assert_eq!(layout.hit(10, STACKS.1 - 1), None);
+    }
+
+    #[test]
+    fn tall_stack_fits_and_every_card_can_be_picked() {
+        let layout = Layout::default();
+        let mut b = Board::from_seed(0);
+        let stack = Stack::from_col(0);
+        //Only where the cards go matters here, not which cards they are
+        let len = 25;
+        b.in_play[0] = vec![Card::JRed; len];
+        assert!(layout.fan(&b, stack) < FAN);
+        for idx in 0..len {
+            assert_eq!(layout.card_at(&b, stack, layout.card(&b, stack, idx).y + 1), Some(idx));
+        }
+        let last = layout.card(&b, stack, len - 1);
+        assert!(last.y + last.h as i32 <= layout.height as i32);
+    }
+}
@@ -0,0 +1,47 @@
+//! A stopwatch for timing the game in progress, which can be paused.
+use std::time::{Duration, Instant};
+
+pub struct Clock {
+    banked: Duration,
+    since: Option<Instant>,
+}
+
+impl Clock {
+    /// A clock at zero, already running.
+    pub fn start() -> Self {
+        Clock::start_at(Duration::from_secs(0))
+    }
+
+    /// A running clock that already reads `elapsed`, for a resumed game.
+    pub fn start_at(elapsed: Duration) -> Self {
+        Clock {
+            banked: elapsed,
+            since: Some(Instant::now()),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.since {
+            Some(since) => self.banked + since.elapsed(),
+            None => self.banked,
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.since.take() {
+            self.banked += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.since.is_none() {
+            self.since = Some(Instant::now());
+        }
+    }
+}
+
+/// Formats a duration as minutes and seconds, e.g. `12:05`.
+pub fn format(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
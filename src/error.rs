@@ -1,17 +1,80 @@
+use std::error;
+use std::fmt;
+
+use ::board::{Card, NumCard};
+
 #[derive(Debug)]
 pub enum Error{
     NothingInUtl,
-    StackCantParent,
+    StackCantParent(Card),
     StackOutOfOrder,
     JacksNotVisible,
     MoveJacks,
     MustClickCard,
+    NotEnoughCards,
+    SameStack,
     MultipleToSlot,
     CardNotNumeric,
     NoCardClicked,
     NoOpenUtility,
-    OrdCantParent,
+    OrdCantParent(NumCard),
     BadSourceOrDest,
+    NowhereToGo,
+    NoFoundation,
     UtlNotOpen,
     InvalidConv,
+    BadCard(String),
+    BadLine(String),
+    DuplicateCard(String),
+    WrongCardCount(usize),
+    BadMove(String),
+    BadRecord(String),
+    BadHistory,
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match *self {
+            NothingInUtl => write!(f, "That utility slot is empty."),
+            StackCantParent(Card::Num(ref c)) if c.value() == 8 =>
+                write!(f, "A 9 can only go on an empty stack."),
+            StackCantParent(Card::Num(ref c)) =>
+                write!(f, "A {} can only go on a {} of a different suit.",
+                       c.value() + 1, c.value() + 2),
+            StackCantParent(_) => write!(f, "Jacks can only go on an empty stack."),
+            StackOutOfOrder =>
+                write!(f, "Only a run of alternating suits in descending order can move together."),
+            JacksNotVisible =>
+                write!(f, "All four jacks of a suit must be uncovered to collapse them."),
+            MoveJacks => write!(f, "Collapsed jacks can't be moved."),
+            MustClickCard => write!(f, "Pick up a card to move it."),
+            NotEnoughCards => write!(f, "There aren't that many cards on the stack."),
+            SameStack => write!(f, "Those cards are already on that stack."),
+            MultipleToSlot => write!(f, "Only one card at a time can go in that slot."),
+            CardNotNumeric => write!(f, "Only numbered cards can go on the ordered stacks."),
+            NoCardClicked => write!(f, "That stack is empty."),
+            NoOpenUtility =>
+                write!(f, "Collapsing jacks needs an open utility slot or one holding one of them."),
+            OrdCantParent(ref c) if c.value() == 0 =>
+                write!(f, "A 1 can only go on an empty ordered stack."),
+            OrdCantParent(ref c) =>
+                write!(f, "A {} can only go on the {} of the same suit in the ordered stacks.",
+                       c.value() + 1, c.value()),
+            BadSourceOrDest => write!(f, "Cards can't move from there to there."),
+            NowhereToGo => write!(f, "That card has nowhere to go on its own."),
+            NoFoundation => write!(f, "That card can't go on any ordered stack yet."),
+            UtlNotOpen => write!(f, "That utility slot is already taken."),
+            InvalidConv => write!(f, "Collapsed jacks aren't a card."),
+            BadCard(ref s) => write!(f, "\"{}\" isn't a card.", s),
+            BadLine(ref s) => write!(f, "Couldn't read \"{}\".", s),
+            DuplicateCard(ref s) => write!(f, "{} appears too many times.", s),
+            WrongCardCount(n) => write!(f, "A position needs 40 cards, not {}.", n),
+            BadMove(ref s) => write!(f, "\"{}\" isn't a move.", s),
+            BadRecord(ref s) => write!(f, "Bad game record: {}.", s),
+            BadHistory => write!(f, "The game's history doesn't lead to its board."),
+        }
+    }
+}
+
+impl error::Error for Error {}
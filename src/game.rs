@@ -0,0 +1,208 @@
+use ::board::{Board, Card};
+use ::gamemove::Move;
+#[cfg(feature = "save")]
+use ::error::Error::BadHistory;
+#[cfg(feature = "save")]
+use ::notation::validate_cards;
+use ::Result;
+
+/// One player move together with everything it changed. `before` is the
+/// exact board the move was made on and `swept` lists, in order, the cards
+/// that `Board::sweep_free` moved afterwards.
+#[derive(Clone)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
+pub struct Turn {
+    pub before: Board,
+    pub mv: Move,
+    pub swept: Vec<Card>,
+}
+
+impl Turn {
+    /// The boards the turn passes through: the one it was made on, the one
+    /// right after the move, and one more after each swept card.
+    pub fn frames(&self) -> Vec<Board> {
+        let mut board = self.before.clone();
+        let mut frames = vec![board.clone()];
+        //The move was valid when it was played, so it still is
+        board.make_move(board.get_valid(self.mv.clone()).unwrap());
+        frames.push(board.clone());
+        for _ in &self.swept {
+            board.sweep_one();
+            frames.push(board.clone());
+        }
+        frames
+    }
+
+    //Replays the turn on `board`, failing unless that is the board it was
+    //made on, the move is valid there and it sweeps the same cards. Returns
+    //the board the turn ends on.
+    #[cfg(feature = "save")]
+    fn replay_on(&self, board: &Board) -> Result<Board> {
+        if *board != self.before { return Err(BadHistory) }
+        let mut board = board.clone();
+        let v = board.get_valid(self.mv.clone())?;
+        board.make_move(v);
+        if board.sweep_free() != self.swept { return Err(BadHistory) }
+        Ok(board)
+    }
+}
+
+/// A board plus its undo/redo history.
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
+pub struct Game {
+    board: Board,
+    done: Vec<Turn>,
+    undone: Vec<Turn>,
+}
+
+impl Game {
+    pub fn new(board: Board) -> Self {
+        Game {
+            board,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The turns played so far, oldest first.
+    pub fn history(&self) -> &[Turn] {
+        &self.done
+    }
+
+    //Validates and makes a move, then sweeps. Returns the resulting turn.
+    fn apply(&mut self, m: Move) -> Result<Turn> {
+        let v = self.board.get_valid(m.clone())?;
+        let before = self.board.clone();
+        self.board.make_move(v);
+        let swept = self.board.sweep_free();
+        Ok(Turn { before, mv: m, swept })
+    }
+
+    /// Plays a move. Making a new move discards anything that could have
+    /// been redone.
+    pub fn play(&mut self, m: Move) -> Result<()> {
+        let turn = self.apply(m)?;
+        self.done.push(turn);
+        self.undone.clear();
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Restores the board to how it was before the last move. Returns false
+    /// if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.done.pop() {
+            Some(turn) => {
+                self.board = turn.before.clone();
+                self.undone.push(turn);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Checks a game that didn't come from `play`, such as one read from a
+    /// file: every board must hold the whole deck, and the turns must replay
+    /// one into the next, the undone ones continuing from the current board.
+    /// Undo, redo and `Turn::frames` rely on this.
+    #[cfg(feature = "save")]
+    pub(crate) fn validate(&self) -> Result<()> {
+        let mut at = self.done.first().map_or(&self.board, |t| &t.before).clone();
+        validate_cards(&at)?;
+        for turn in &self.done {
+            at = turn.replay_on(&at)?;
+        }
+        if at != self.board { return Err(BadHistory) }
+        for turn in self.undone.iter().rev() {
+            at = turn.replay_on(&at)?;
+        }
+        Ok(())
+    }
+
+    /// Replays the most recently undone move. Returns false if there is
+    /// nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            //Redoing an undone move on the board it was made on can't fail
+            Some(turn) => {
+                let turn = self.apply(turn.mv).unwrap();
+                self.done.push(turn);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::solver::{self, Solution};
+
+    //Deal 1 and its winning line, which the solver settles quickly
+    fn deal_1() -> (Board, Vec<Move>) {
+        let board = Board::from_seed(1);
+        match solver::solve(&board, solver::DEFAULT_NODE_BUDGET) {
+            Solution::Solved(moves) => (board, moves),
+            other => panic!("deal 1 should be solved, got {:?}", other),
+        }
+    }
+
+    //Plays the line up to and including the first move that sweeps cards
+    fn play_to_sweep(game: &mut Game, line: &[Move]) -> Board {
+        for m in line {
+            let before = game.board().clone();
+            game.play(m.clone()).unwrap();
+            if !game.history().last().unwrap().swept.is_empty() {
+                return before;
+            }
+        }
+        panic!("the line never sweeps a card");
+    }
+
+    #[test]
+    fn undo_restores_swept_cards() {
+        let (board, line) = deal_1();
+        let mut game = Game::new(board);
+        let before = play_to_sweep(&mut game, &line);
+        assert!(game.undo());
+        assert_eq!(*game.board(), before);
+    }
+
+    #[test]
+    fn redo_brings_back_the_same_board() {
+        let (board, line) = deal_1();
+        let mut game = Game::new(board);
+        play_to_sweep(&mut game, &line);
+        let after = game.board().clone();
+        game.undo();
+        assert!(game.redo());
+        assert_eq!(*game.board(), after);
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn playing_clears_redo() {
+        let (board, line) = deal_1();
+        let mut game = Game::new(board);
+        game.play(line[0].clone()).unwrap();
+        game.play(line[1].clone()).unwrap();
+        game.undo();
+        assert!(game.can_redo());
+        game.undo();
+        game.play(line[0].clone()).unwrap();
+        assert!(!game.can_redo());
+        assert!(!game.redo());
+    }
+}
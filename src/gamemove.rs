@@ -1,52 +1,162 @@
-pub static STACKS: (i32, i32) = (0, 110);
-pub static UTILITIES: (i32, i32) = (0, 0);
-pub static BUTTONS: (i32, i32) = (225, 0);
-pub static ROSE: (i32, i32) = (300, 0);
-pub static ORDERED: (i32, i32) = (375, 0);
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct StackPosition {
-    pub stack: Stack,
-    pub y: u32, 
-}
+use ::board::{Board, Suit};
+use ::error::Error::*;
+use ::Result;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Ordered {
     Ordered0 = 0,
     Ordered1 = 1,
     Ordered2 = 2,
 }
 
+impl Ordered {
+    pub fn from_idx(idx: usize) -> Ordered {
+        use self::Ordered::*;
+        match idx {
+            0 => Ordered0,
+            1 => Ordered1,
+            2 => Ordered2,
+            _ => panic!("Attempted to convert a usize to an ordered slot that doesn't exist."),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Utility {
     Utility0 = 0,
     Utility1 = 1,
     Utility2 = 2,
 }
 
+impl Utility {
+    pub fn from_idx(idx: usize) -> Utility {
+        use self::Utility::*;
+        match idx {
+            0 => Utility0,
+            1 => Utility1,
+            2 => Utility2,
+            _ => panic!("Attempted to convert a usize to a utility that doesn't exist."),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Attempt(pub Move);
 
+/// A move `Board::get_valid` has checked, which is the only way to get one.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Valid(pub Move);
+pub struct Valid(pub(crate) Move);
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub struct Move {
-    pub src: Option<ClickTarget>,
-    pub dst: Option<ClickTarget>,
+impl Valid {
+    pub fn mv(&self) -> &Move {
+        &self.0
+    }
 }
 
+/// A move expressed purely in terms of the board, independent of where
+/// anything is drawn. `depth` is the number of cards taken off the top of
+/// the source stack.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub enum ClickTarget {
-    Stack(StackPosition),
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
+pub enum Move {
+    StackToStack { src: Stack, depth: usize, dst: Stack },
+    StackToUtility { src: Stack, dst: Utility },
+    StackToFoundation { src: Stack, dst: Ordered },
+    UtilityToStack { src: Utility, dst: Stack },
+    UtilityToFoundation { src: Utility, dst: Ordered },
+    CollapseJacks(Suit),
+}
+
+impl Move {
+    /// Resolves a picked-up source and a drop destination into the move
+    /// they describe. The move still has to be checked with
+    /// `Board::get_valid`.
+    pub fn between(b: &Board, src: Option<&Target>, dst: Option<&Target>) -> Result<Move> {
+        use self::Target::*;
+
+        //Only the last card in a stack can move to a utility or ordered slot
+        let last = |stack: self::Stack, idx: usize| {
+            if b.in_play[stack as usize].len() != idx + 1 { Err(MultipleToSlot) } else { Ok(()) }
+        };
+
+        Ok(match (src, dst) {
+            (_, Some(&Jacks(s))) => Move::CollapseJacks(s),
+            (Some(&Utility(src)), Some(&Stack { stack: dst, .. })) =>
+                Move::UtilityToStack { src, dst },
+            (Some(&Utility(src)), Some(&Ordered(dst))) =>
+                Move::UtilityToFoundation { src, dst },
+            (Some(&Stack { stack: src, idx }), Some(&Stack { stack: dst, .. })) => {
+                let len = b.in_play[src as usize].len();
+                if idx >= len { return Err(MustClickCard) }
+                Move::StackToStack { src, depth: len - idx, dst }
+            },
+            (Some(&Stack { stack: src, idx }), Some(&Ordered(dst))) => {
+                last(src, idx)?;
+                Move::StackToFoundation { src, dst }
+            },
+            (Some(&Stack { stack: src, idx }), Some(&Utility(dst))) => {
+                last(src, idx)?;
+                Move::StackToUtility { src, dst }
+            },
+            _ => return Err(BadSourceOrDest),
+        })
+    }
+
+    /// The move that puts the card at `src`, the top card of a stack or a
+    /// utility card, on whichever ordered stack takes it.
+    pub fn to_foundation(b: &Board, src: &Target) -> Result<Move> {
+        match *src {
+            Target::Stack { stack, idx } => {
+                let len = b.in_play[stack as usize].len();
+                if idx >= len { return Err(NoCardClicked) }
+                if idx + 1 != len { return Err(MultipleToSlot) }
+                fitting(b, |dst| Move::StackToFoundation { src: stack, dst })
+                    .ok_or(NoFoundation)
+            },
+            Target::Utility(u) => {
+                fitting(b, |dst| Move::UtilityToFoundation { src: u, dst })
+                    .ok_or(NoFoundation)
+            },
+            _ => Err(BadSourceOrDest),
+        }
+    }
+
+    /// The move that sends a card off on its own, as on a double-click. It
+    /// goes to the ordered stacks if it fits there; failing that, a stack's
+    /// top card goes to the first open utility slot.
+    pub fn auto(b: &Board, src: &Target) -> Result<Move> {
+        match (Move::to_foundation(b, src), *src) {
+            (Err(NoFoundation), Target::Stack { stack, .. }) => {
+                b.utility.iter().position(|u| u.is_none())
+                    .map(|u| Move::StackToUtility { src: stack, dst: Utility::from_idx(u) })
+                    .ok_or(NowhereToGo)
+            },
+            (Err(NoFoundation), _) => Err(NowhereToGo),
+            (found, _) => found,
+        }
+    }
+}
+
+//The first move built by `to` that is legal, trying each ordered stack
+fn fitting<F: Fn(Ordered) -> Move>(b: &Board, to: F) -> Option<Move> {
+    (0..3).map(Ordered::from_idx).map(to).find(|m| b.get_valid(m.clone()).is_ok())
+}
+
+/// Something a player can pick cards up from or drop them on, whether they
+/// chose it with the mouse or the keyboard. For stacks, `idx` is the index
+/// of the chosen card.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Target {
+    Stack { stack: Stack, idx: usize },
     Utility(Utility),
     Ordered(Ordered),
-    GreenButton,
-    RedButton,
-    BlackButton,
+    Jacks(Suit),
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "save", derive(Serialize, Deserialize))]
 pub enum Stack {
     Stack0 = 0,
     Stack1 = 1,
@@ -74,31 +184,3 @@ impl Stack {
         }
     }
 }
-
-impl ClickTarget {
-    pub fn from_coord(x: i32, y: i32) -> Option<ClickTarget> {
-        use self::ClickTarget::*;
-        use self::Utility::*;
-        use self::Ordered::*;
-        use self::{Stack, StackPosition};
-        Some(match (x, y) {
-            (0...70, 0...102) => Utility(Utility0),
-            (75...145, 0...102) => Utility(Utility1),
-            (150...220, 0...102) => Utility(Utility2),
-            (375...445, 0...102) => Ordered(Ordered0),
-            (450...520, 0...102) => Ordered(Ordered1),
-            (525...595, 0...102) => Ordered(Ordered2),
-            (245...275, 0...30) => RedButton,
-            (245...275, 35...65) => GreenButton,
-            (245...275, 70...100) => BlackButton,
-            (0...600, y) => {
-                Stack(StackPosition{
-                    stack: Stack::from_col((x as usize) / 75),
-                    y: y as u32,
-                })
-            },
-            (_, _) => return None,
-        })
-    }
-}
-
@@ -1,71 +1,328 @@
 use ::orbtk::{Renderer, Color, Point};
-use ::board::Board;
-use ::gamemove::{STACKS, UTILITIES, ROSE, ORDERED, BUTTONS};
+use ::cvsolitaire::board::{Board, Card, CardOrJacks, Jacks, Suit};
+use ::cvsolitaire::gamemove::{Move, Ordered, Stack, Target, Utility};
+use ::cvsolitaire::click::{Area, Layout, CARD};
+use ::orbimage::{Image, ResizeType};
+
+use std::cell::RefCell;
 
 lazy_static! {
-    static ref SSHEET: ::orbimage::Image =
+    static ref SSHEET: Image =
         ::orbimage::parse_png(include_bytes!("../assets/cards.png")).unwrap();
 }
 
-pub fn render_cursor<R: Renderer> (r: &mut R, p: Option<&Point>) {
+thread_local! {
+    //The sprite sheet resized for the last scale other than 1 drawn at
+    static SCALED: RefCell<Option<(f32, Image)>> = const { RefCell::new(None) };
+}
+
+//Lends out the sprite sheet at `scale`, resizing it only when that changes
+fn with_sheet<F: FnOnce(&Image)>(scale: f32, f: F) {
+    if scale == 1.0 { return f(&SSHEET) }
+    SCALED.with(|scaled| {
+        let mut scaled = scaled.borrow_mut();
+        if !matches!(*scaled, Some((s, _)) if s == scale) {
+            let w = (SSHEET.width() as f32 * scale).round() as u32;
+            let h = (SSHEET.height() as f32 * scale).round() as u32;
+            //A sheet too small to resize to has nothing worth drawing
+            *scaled = SSHEET.resize(w, h, ResizeType::Lanczos3).ok().map(|sheet| (scale, sheet));
+        }
+        if let Some((_, ref sheet)) = *scaled {
+            f(sheet);
+        }
+    })
+}
+
+/// A renderer seen through a layout. Everything is drawn in board
+/// coordinates and comes out scaled to fit the canvas.
+pub struct Surface<'a, R: Renderer + 'a> {
+    r: &'a mut R,
+    layout: Layout,
+}
+
+impl<'a, R: Renderer> Surface<'a, R> {
+    pub fn new(r: &'a mut R, layout: Layout) -> Self {
+        Surface { r, layout }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn set(&mut self, color: Color) {
+        self.r.set(color);
+    }
+
+    pub fn rect(&mut self, x: i32, y: i32, w: u32, h: u32, color: Color) {
+        let (x0, y0) = self.layout.to_canvas(x, y);
+        let (x1, y1) = self.layout.to_canvas(x + w as i32, y + h as i32);
+        self.r.rect(x0, y0, (x1 - x0) as u32, (y1 - y0) as u32, color);
+    }
+
+    //Draws the `w` by `h` part of the sprite sheet at (`sx`, `sy`)
+    fn sprite(&mut self, sx: u32, sy: u32, w: u32, h: u32, x: i32, y: i32) {
+        let scale = self.layout.scale;
+        let (x0, y0) = self.layout.to_canvas(x, y);
+        let (x1, y1) = self.layout.to_canvas(x + w as i32, y + h as i32);
+        let s = |v: u32| (v as f32 * scale).round() as u32;
+        let r = &mut *self.r;
+        with_sheet(scale, |sheet| {
+            sheet.roi(s(sx), s(sy), (x1 - x0) as u32, (y1 - y0) as u32).draw(r, x0, y0);
+        });
+    }
+}
+
+pub fn render_cursor<R: Renderer> (r: &mut Surface<R>, p: Option<&Point>) {
     if let Some(p) = p {
-        SSHEET.roi(70, 612, 30, 30)
-            .draw(r, p.x - 15, p.y - 15);
+        r.sprite(70, 612, 30, 30, p.x - 15, p.y - 15);
+    }
+}
+
+//Source and destination areas of a move
+fn move_areas(l: &Layout, b: &Board, m: &Move) -> (Area, Area) {
+    let top = |s: Stack| l.run(b, s, b.in_play[s as usize].len().saturating_sub(1));
+    match *m {
+        Move::StackToStack { src, depth, dst } => {
+            let len = b.in_play[src as usize].len();
+            (l.run(b, src, len.saturating_sub(depth)), top(dst))
+        },
+        Move::StackToUtility { src, dst } => (top(src), l.utility(dst)),
+        Move::StackToFoundation { src, dst } => (top(src), l.ordered(dst)),
+        Move::UtilityToStack { src, dst } => (l.utility(src), top(dst)),
+        Move::UtilityToFoundation { src, dst } => (l.utility(src), l.ordered(dst)),
+        Move::CollapseJacks(s) => (l.button(s), l.button(s)),
+    }
+}
+
+/// Shades the source and destination of a hinted move.
+pub fn render_hint<R: Renderer>(r: &mut Surface<R>, b: &Board, m: &Move) {
+    let (src, dst) = move_areas(&r.layout(), b, m);
+    for a in &[src, dst] {
+        r.rect(a.x, a.y, a.w, a.h, Color::rgba(255, 255, 0, 96));
+    }
+}
+
+/// Draws the board with the cards picked up at `src` moved by `offset`,
+/// following the pointer. Only stacks and utility cards can be picked up;
+/// for anything else the board is drawn as it is.
+pub fn render_lifted<R: Renderer>(r: &mut Surface<R>, b: &Board, src: &Target, offset: Point) {
+    let l = r.layout();
+    let mut rest = b.clone();
+    //The cards, each with where it is normally drawn
+    let cards: Vec<(Card, Area)> = match *src {
+        Target::Stack { stack, idx } if idx < b.in_play[stack as usize].len() => {
+            rest.in_play[stack as usize].split_off(idx).into_iter().enumerate()
+                .map(|(row, c)| (c, l.card(b, stack, idx + row)))
+                .collect()
+        },
+        Target::Utility(u) => match rest.utility[u as usize].take() {
+            Some(CardOrJacks::Card(c)) => vec![(c, l.utility(u))],
+            _ => return render(r, b),
+        },
+        _ => return render(r, b),
+    };
+    render_over(r, &rest, b);
+    for &(ref card, a) in &cards {
+        draw_card(r, card, a.x + offset.x, a.y + offset.y);
     }
 }
 
-pub fn render<R: Renderer> (r: &mut R, b: &Board) {
+/// Draws the way from `from` to `to`, `t` of the way along: cards that
+/// moved are drawn in between where they were and where they end up.
+pub fn render_tween<R: Renderer>(r: &mut Surface<R>, from: &Board, to: &Board, t: f32) {
+    let l = r.layout();
+    let start = card_spots(&l, from);
+    let end = card_spots(&l, to);
+    let mut claimed = vec![false; start.len()];
+    //Where each card in `to` moved from, if it moved
+    let mut origin = vec![None; end.len()];
+
+    //Match up cards that stayed put first, so identical jacks elsewhere
+    //aren't mistaken for them
+    let mut stayed = vec![false; end.len()];
+    for (i, &(ref card, spot)) in end.iter().enumerate() {
+        let same = (0..start.len())
+            .find(|&j| !claimed[j] && start[j].0 == *card && start[j].1 == spot);
+        if let Some(j) = same {
+            claimed[j] = true;
+            stayed[i] = true;
+        }
+    }
+    for (i, (card, _)) in end.iter().enumerate() {
+        if stayed[i] { continue }
+        if let Some(j) = (0..start.len()).find(|&j| !claimed[j] && start[j].0 == *card) {
+            claimed[j] = true;
+            origin[i] = Some(start[j].1);
+        }
+    }
+
+    let moving: Vec<bool> = origin.iter().map(|o| o.is_some()).collect();
+    render_over(r, &without(to, &moving), to);
+    for (&(ref card, (x1, y1)), o) in end.iter().zip(&origin) {
+        if let Some((x0, y0)) = *o {
+            let x = x0 + ((x1 - x0) as f32 * t) as i32;
+            let y = y0 + ((y1 - y0) as f32 * t) as i32;
+            draw_card(r, card, x, y);
+        }
+    }
+}
+
+//Where each card on the board is drawn, in drawing order. Collapsed jacks
+//count as their four cards, all on the bundle's slot.
+fn card_spots(l: &Layout, b: &Board) -> Vec<(Card, (i32, i32))> {
+    let mut spots = Vec::new();
+    for (col, stack) in b.in_play.iter().enumerate() {
+        for (row, card) in stack.iter().enumerate() {
+            let a = l.card(b, Stack::from_col(col), row);
+            spots.push((card.clone(), (a.x, a.y)));
+        }
+    }
+    for (idx, slot) in b.utility.iter().enumerate() {
+        let Area { x, y, .. } = l.utility(Utility::from_idx(idx));
+        match *slot {
+            Some(CardOrJacks::Card(ref c)) => spots.push((c.clone(), (x, y))),
+            Some(CardOrJacks::Jacks(ref j)) => {
+                let jack = match *j {
+                    Jacks::Red => Card::JRed,
+                    Jacks::Green => Card::JGreen,
+                    Jacks::Black => Card::JBlack,
+                };
+                for _ in 0..4 {
+                    spots.push((jack.clone(), (x, y)));
+                }
+            },
+            None => (),
+        }
+    }
+    for (idx, ordered) in b.ordered.iter().enumerate() {
+        let Area { x, y, .. } = l.ordered(Ordered::from_idx(idx));
+        for c in ordered {
+            spots.push((Card::Num(c.clone()), (x, y)));
+        }
+    }
+    if let Some(ref joker) = b.joker {
+        let rose = l.rose();
+        spots.push((joker.clone(), (rose.x, rose.y)));
+    }
+    spots
+}
+
+//The board without the cards flagged in `gone`, which lines up with
+//`card_spots`. Moving cards are always on top of wherever they are going.
+fn without(b: &Board, gone: &[bool]) -> Board {
+    let mut rest = b.clone();
+    let mut gone = gone.iter().cloned();
+    for stack in rest.in_play.iter_mut() {
+        let flags: Vec<bool> = gone.by_ref().take(stack.len()).collect();
+        if let Some(first) = flags.iter().position(|g| *g) {
+            stack.truncate(first);
+        }
+    }
+    for slot in rest.utility.iter_mut() {
+        let n = match *slot {
+            Some(CardOrJacks::Jacks(_)) => 4,
+            Some(CardOrJacks::Card(_)) => 1,
+            None => 0,
+        };
+        let flags: Vec<bool> = gone.by_ref().take(n).collect();
+        if flags.iter().any(|g| *g) {
+            *slot = None;
+        }
+    }
+    for ordered in rest.ordered.iter_mut() {
+        let flags: Vec<bool> = gone.by_ref().take(ordered.len()).collect();
+        if let Some(first) = flags.iter().position(|g| *g) {
+            ordered.truncate(first);
+        }
+    }
+    if gone.next() == Some(true) {
+        rest.joker = None;
+    }
+    rest
+}
+
+fn draw_card<R: Renderer>(r: &mut Surface<R>, card: &Card, x: i32, y: i32) {
+    let (rmap, cmap) = card.map_coords();
+    r.sprite(cmap as u32, rmap as u32, CARD.0, CARD.1, x, y);
+}
+
+/// Outlines the cards picked up from the keyboard.
+pub fn render_focus<R: Renderer>(r: &mut Surface<R>, b: &Board, t: &Target) {
+    let l = r.layout();
+    let Area { x, y, w, h } = match *t {
+        Target::Stack { stack, idx } => l.run(b, stack, idx),
+        Target::Utility(u) => l.utility(u),
+        Target::Ordered(o) => l.ordered(o),
+        Target::Jacks(s) => l.button(s),
+    };
+    let color = Color::rgb(0, 120, 255);
+    r.rect(x - 3, y - 3, w + 6, 3, color);
+    r.rect(x - 3, y + h as i32, w + 6, 3, color);
+    r.rect(x - 3, y, 3, h, color);
+    r.rect(x + w as i32, y, 3, h, color);
+}
+
+pub fn render<R: Renderer> (r: &mut Surface<R>, b: &Board) {
+    render_over(r, b, b);
+}
+
+//Draws `b` with its stacks laid out as they are on `shape`, which `b` is
+//part of. That keeps cards still when others are lifted off their stack
+//and it no longer needs squeezing.
+fn render_over<R: Renderer>(r: &mut Surface<R>, b: &Board, shape: &Board) {
     r.set(Color::rgba(255,255,255, 0));
-    render_stacks(r, b);
+    render_stacks(r, b, shape);
     render_joker(r, b);
     render_ordered(r, b);
     render_utilities(r, b);
     render_buttons(r, b);
 }
 
-pub fn render_stacks<R: Renderer>(r: &mut R, b: &Board)  {
-    let rows = b.in_play.iter().map(|v| v.len()).max().unwrap();
-    for row in 0..rows {
-        for col in 0..8 {
-            if let Some(card) = b.in_play[col].get(row) {
-                let (rmap, cmap) = card.map_coords();
-                SSHEET.roi(cmap as u32, rmap as u32, 70, 102)
-                    .draw(r, (col as i32 * 75) + STACKS.0, (row as i32 * 20) + STACKS.1);
-            }
+fn render_stacks<R: Renderer>(r: &mut Surface<R>, b: &Board, shape: &Board)  {
+    let l = r.layout();
+    for (col, stack) in b.in_play.iter().enumerate() {
+        for (row, card) in stack.iter().enumerate() {
+            let a = l.card(shape, Stack::from_col(col), row);
+            draw_card(r, card, a.x, a.y);
         }
     }
 }
 
-pub fn render_joker<R: Renderer>(r: &mut R, b: &Board)  {
+pub fn render_joker<R: Renderer>(r: &mut Surface<R>, b: &Board)  {
     let (rmap, cmap) = if let Some(ref r) = b.joker {
         r.map_coords()
     } else { (510, 0) };
-    SSHEET.roi(cmap as u32, rmap as u32, 70, 102).draw(r, ROSE.0, ROSE.1);
+    let rose = r.layout().rose();
+    r.sprite(cmap as u32, rmap as u32, CARD.0, CARD.1, rose.x, rose.y);
 }
 
-pub fn render_ordered<R: Renderer>(r: &mut R, b: &Board)  {
+pub fn render_ordered<R: Renderer>(r: &mut Surface<R>, b: &Board)  {
     for (idx, slot) in b.ordered.iter().enumerate() {
         let (rmap, cmap) = if let Some(c) = slot.last() {
             if c.value() == 8 { (612, 0) } else { c.map_coords() }
         } else { (510, 0) };
-        SSHEET.roi(cmap as u32, rmap as u32, 70, 102)
-            .draw(r, ORDERED.0 + (idx as i32 * 75), ORDERED.1);
+        let a = r.layout().ordered(Ordered::from_idx(idx));
+        r.sprite(cmap as u32, rmap as u32, CARD.0, CARD.1, a.x, a.y);
     }
 }
 
-pub fn render_buttons<R: Renderer>(r: &mut R, _b: &Board)  {
-    r.rect(BUTTONS.0 + 20, BUTTONS.1 + 0, 30, 30, Color::rgb(223, 0, 0));
-    r.rect(BUTTONS.0 + 20, BUTTONS.1 + 35, 30, 30, Color::rgb(0, 160, 0));
-    r.rect(BUTTONS.0 + 20, BUTTONS.1 + 70, 30, 30, Color::rgb(0, 0, 0));
+pub fn render_buttons<R: Renderer>(r: &mut Surface<R>, _b: &Board)  {
+    let buttons = [(Suit::Red, Color::rgb(223, 0, 0)),
+                   (Suit::Green, Color::rgb(0, 160, 0)),
+                   (Suit::Black, Color::rgb(0, 0, 0))];
+    for &(suit, color) in &buttons {
+        let a = r.layout().button(suit);
+        r.rect(a.x, a.y, a.w, a.h, color);
+    }
 }
 
-pub fn render_utilities<R: Renderer>(r: &mut R, b: &Board)  {
+pub fn render_utilities<R: Renderer>(r: &mut Surface<R>, b: &Board)  {
     for (idx, slot) in b.utility.iter().enumerate() {
         let (rmap, cmap) = if let Some(ref cod) = *slot {
             cod.map_coords()
         } else { (510, 0) };
-        SSHEET.roi(cmap as u32, rmap as u32, 70, 102)
-            .draw(r, UTILITIES.0 + (idx as i32 * 75), UTILITIES.1);
+        let a = r.layout().utility(Utility::from_idx(idx));
+        r.sprite(cmap as u32, rmap as u32, CARD.0, CARD.1, a.x, a.y);
     }
 }
 
@@ -77,9 +334,9 @@ trait SpriteMapped {
     }
 }
 
-impl SpriteMapped for ::board::NumCard {
+impl SpriteMapped for ::cvsolitaire::board::NumCard {
     fn sprite_map(&self) -> (usize, usize) {
-        use ::board::NumCard;
+        use ::cvsolitaire::board::NumCard;
         match *self {
             NumCard::Red(0) => (4, 6),
             NumCard::Green(0) => (4, 5),
@@ -116,9 +373,9 @@ impl SpriteMapped for ::board::NumCard {
     }
 }
 
-impl SpriteMapped for ::board::Card {
+impl SpriteMapped for ::cvsolitaire::board::Card {
     fn sprite_map(&self) -> (usize, usize) {
-        use ::board::Card;
+        use ::cvsolitaire::board::Card;
         match *self {
             Card::JRed => (5, 3),
             Card::JGreen => (5, 2),
@@ -129,9 +386,9 @@ impl SpriteMapped for ::board::Card {
     }
 }
 
-impl SpriteMapped for ::board::CardOrJacks {
+impl SpriteMapped for ::cvsolitaire::board::CardOrJacks {
     fn sprite_map(&self) -> (usize, usize) {
-        use ::board::CardOrJacks;
+        use ::cvsolitaire::board::CardOrJacks;
         match *self {
             CardOrJacks::Card(ref c) => c.sprite_map(),
             CardOrJacks::Jacks(ref d) => d.sprite_map(),
@@ -139,9 +396,9 @@ impl SpriteMapped for ::board::CardOrJacks {
     }
 }
 
-impl SpriteMapped for ::board::Jacks {
+impl SpriteMapped for ::cvsolitaire::board::Jacks {
     fn sprite_map(&self) -> (usize, usize) {
-        use ::board::Jacks;
+        use ::cvsolitaire::board::Jacks;
         match *self {
             Jacks::Red => (6, 0),
             Jacks::Green => (6, 0),
@@ -0,0 +1,81 @@
+//! An invisible widget that forwards key presses to a callback, so the game
+//! can be driven from the keyboard no matter which widget has focus.
+use orbtk::{Event, Rect, Renderer, Widget};
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    F2,
+}
+
+type Handler = Box<dyn Fn(Key)>;
+
+pub struct KeyListener {
+    rect: Cell<Rect>,
+    on_key: RefCell<Option<Handler>>,
+}
+
+impl KeyListener {
+    //orbtk keeps widgets in Arcs, even ones that never leave the UI thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn new() -> Arc<Self> {
+        Arc::new(KeyListener {
+            rect: Cell::new(Rect::new(0, 0, 0, 0)),
+            on_key: RefCell::new(None),
+        })
+    }
+
+    pub fn on_key<F: Fn(Key) + 'static>(&self, func: F) -> &Self {
+        *self.on_key.borrow_mut() = Some(Box::new(func));
+        self
+    }
+
+    fn emit(&self, key: Key) {
+        if let Some(ref func) = *self.on_key.borrow() {
+            func(key);
+        }
+    }
+}
+
+impl Widget for KeyListener {
+    fn rect(&self) -> &Cell<Rect> {
+        &self.rect
+    }
+
+    fn name(&self) -> &str {
+        "KeyListener"
+    }
+
+    fn draw(&self, _renderer: &mut dyn Renderer, _focused: bool) {}
+
+    fn event(&self, event: Event, _focused: bool, redraw: &mut bool) -> bool {
+        let key = match event {
+            //Ctrl+letter arrives as the matching ASCII control character
+            Event::Text { c } if ('\u{1}'..='\u{1a}').contains(&c) =>
+                Key::Ctrl((b'a' + (c as u8) - 1) as char),
+            Event::Text { c: '\u{1b}' } => Key::Esc,
+            Event::Text { c } => Key::Char(c),
+            Event::Enter => Key::Enter,
+            Event::UpArrow => Key::Up,
+            Event::DownArrow => Key::Down,
+            Event::LeftArrow => Key::Left,
+            Event::RightArrow => Key::Right,
+            //Keys orbtk has no event for come through as raw key events
+            Event::Key(k) if k.pressed && k.scancode == ::orbclient::K_F2 => Key::F2,
+            _ => return false,
+        };
+        self.emit(key);
+        *redraw = true;
+        false
+    }
+}
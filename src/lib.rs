@@ -0,0 +1,32 @@
+extern crate rand;
+#[cfg(feature = "save")]
+extern crate serde;
+#[cfg(feature = "save")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "save")]
+extern crate serde_json;
+
+pub mod board;
+pub mod gamemove;
+pub mod click;
+pub mod error;
+pub mod game;
+pub mod notation;
+pub mod record;
+pub mod replay;
+mod rng;
+pub mod solver;
+#[cfg(feature = "save")]
+pub mod save;
+#[cfg(feature = "save")]
+pub mod settings;
+#[cfg(feature = "save")]
+pub mod stats;
+
+pub use board::{Board, Card, NumCard, Suit, GameStatus};
+pub use gamemove::{Move, Valid};
+pub use error::Error;
+pub use game::{Game, Turn};
+
+pub type Result<T> = std::result::Result<T, error::Error>;
@@ -1,4 +1,4 @@
-extern crate rand;
+extern crate cvsolitaire;
 extern crate orbtk;
 extern crate orbimage;
 extern crate orbclient;
@@ -7,65 +7,121 @@ extern crate orbtk_simple_modal;
 #[macro_use]
 extern crate lazy_static;
 
-mod board;
-mod gamemove;
+mod animation;
+mod app;
+mod clock;
 mod graphics;
-mod error;
+mod keys;
+mod pointer;
+mod viewer;
 
+use app::{App, deal, load_saved, load_settings};
+use cvsolitaire::{board, Game, Suit};
+use cvsolitaire::click::BOARD_SIZE;
+use cvsolitaire::gamemove::{Stack, Utility};
+use keys::{Key, KeyListener};
+use pointer::{Pointer, PointerListener};
+use orbimage::ResizeType;
 use orbtk_simple_modal::Modal;
 
-use orbtk::{Window, Rect, Image, Color, Point, Menu, Action};
-use orbtk::traits::{Click, Place, Text};
+use orbtk::{Window, Rect, Image, Color, Point, Menu, Action, TextBox, Label};
+use orbtk::traits::{Click, Enter, Place, Text};
 
+use std::env;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::thread;
+use std::time::Duration;
 
-pub type Result<T> = std::result::Result<T, error::Error>;
+fn title(seed: Option<u64>) -> String {
+    match seed {
+        Some(seed) => format!("Charles Village Solitaire - Deal #{}", seed),
+        None => "Charles Village Solitaire".to_string(),
+    }
+}
+
+//How much bigger than normal to draw everything, e.g. 2 on a HiDPI screen
+fn hidpi_scale() -> f32 {
+    env::var("CVSOLITAIRE_SCALE").ok()
+        .and_then(|s| s.parse().ok())
+        .and_then(|s: f32| if s > 0.0 { Some(s) } else { None })
+        .unwrap_or(1.0)
+}
+
+//The part of a window of the given size left for the canvas, between the
+//menu bar above and the status bar below
+fn canvas_size(width: u32, height: u32) -> (u32, u32) {
+    (width.saturating_sub(20), height.saturating_sub(16 + 22))
+}
+
+fn winnable_label(winnable_only: bool) -> &'static str {
+    if winnable_only { "Winnable Deals Only: On" } else { "Winnable Deals Only: Off" }
+}
+
+//Starts the deal typed into the deal box, if it holds a valid number, and
+//empties the box
+fn new_game_by_number(app: &App, deal_box: &TextBox) {
+    let text = deal_box.text.get();
+    match text.trim().parse() {
+        Ok(seed) => app.new_game(board::Board::from_seed(seed)),
+        Err(_) if text.trim().is_empty() =>
+            app.set_status("Type a deal number into the box at the top first."),
+        Err(_) => app.set_status(format!("\"{}\" isn't a deal number.", text.trim())),
+    }
+    deal_box.text("");
+}
 
 fn main() {
+    //Given a game record, open it in replay mode instead
+    if let Some(path) = env::args().nth(1) {
+        viewer::run(&path);
+        return;
+    }
+
     //Set up menu bar
     let menu = Menu::new("Menu");
     menu.position(10, 0).size(32, 16);
 
-    //Create representation of game board
-    let board = Rc::new(RefCell::new(board::Board::new()));
+    //Deal number entry for "New Game by Number..."
+    let deal_box = TextBox::new();
+    deal_box.position(50, 0).size(160, 16);
+
+    //Create game window, picking up where the last session left off. It
+    //starts out fitting the board at the screen's scale, and the board
+    //scales along with it when it's resized.
+    let (game, elapsed) = load_saved().unwrap_or_else(|| {
+        (Game::new(deal(load_settings().winnable_only)), Duration::from_secs(0))
+    });
+    let scale = hidpi_scale();
+    let width = (BOARD_SIZE.0 as f32 * scale).round() as u32 + 20;
+    let height = (BOARD_SIZE.1 as f32 * scale).round() as u32 + 16 + 22;
+    let mut window = Window::new_flags(Rect::new(100, 100, width, height),
+                                       &title(game.board().seed),
+                                       &[orbclient::WindowFlag::Async,
+                                         orbclient::WindowFlag::Resizable]);
+    let bg_image = orbimage::parse_png(include_bytes!("../assets/bg.png")).unwrap();
+    let bg = Image::from_image(bg_image.resize(width, height, ResizeType::Lanczos3).unwrap());
+    let canvas = Image::from_color(BOARD_SIZE.0, BOARD_SIZE.1, Color::rgba(255, 255, 255, 0));
 
-    //Track prior click
-    let last: Rc<RefCell<Option<Point>>> = Rc::new(RefCell::new(None));
+    //Shown when a game is won or stuck
+    let end_box = Modal::new();
+    end_box.position(5, 10).size(605, 395);
 
-    //Create game window
-    let mut window = Window::new_flags(Rect::new(100, 100, 615, 420),
-                                       "Charles Village Solitaire",
-                                       &[orbclient::WindowFlag::Async]);
-    let bg =
-        Image::from_image(orbimage::parse_png(include_bytes!("../assets/bg.png")).unwrap());
-    let canvas = Image::from_color(595, 430, Color::rgba(255, 255, 255, 0));
+    //Status bar for the timer, move count, deal and messages
+    let status = Label::new();
 
-    //Render initial game state
-    graphics::render(&mut *canvas.image.borrow_mut(), &*board.borrow());
+    //Create representation of game board and render initial game state
+    let app = Rc::new(App::new(game, elapsed, canvas.clone(), end_box.clone(), status.clone()));
+    app.redraw();
+    app.tick(false);
 
-    //Main game logic
+    //Main game logic, by clicking cards or dragging them
+    canvas.position(10, 16);
+    let pointer_listener = PointerListener::new();
+    pointer_listener.position(10, 16);
     {
-        let last = last.clone();
-        let board = board.clone();   
-        canvas.position(10, 16).on_click(move |canvas: &Image, point: Point| {
-            let mut last_maybe = last.borrow_mut();                
-            let mut board = board.borrow_mut();
-            if let Ok(v) = board.get_valid(
-                gamemove::Move{
-                    src: last_maybe.and_then(|l|gamemove::ClickTarget::from_coord(l.x, l.y)),
-                    dst: gamemove::ClickTarget::from_coord(point.x, point.y),
-                }) {
-                board.make_move(v);
-                board.sweep_free();
-                *last_maybe = None;
-            } else if last_maybe.is_none() {
-                *last_maybe = Some(point);
-            } 
-            else { *last_maybe = None; }
-            let canvas = &mut *canvas.image.borrow_mut();
-            graphics::render(canvas, &board);
-            graphics::render_cursor(canvas, (*last_maybe).as_ref());
+        let app = app.clone();
+        pointer_listener.on_pointer(move |pointer: Pointer| {
+            app.pointer(pointer);
         });
     }
 
@@ -79,51 +135,184 @@ fn main() {
     rules_box.text(include_str!("../assets/rules.txt"))
         .position(5, 10)
         .size(605, 395);
+
+    let stats_box = Modal::new();
+    stats_box.position(5, 10).size(605, 395);
     
     //Menu logic
     {
         let about_box = about_box.clone();
         let rules_box = rules_box.clone();
-        let board = board.clone();
-        let canvas = canvas.clone();
-        let last_ng = last.clone();
-        let last_abt = last.clone();
-        let last_rls = last.clone();
+        let stats_box = stats_box.clone();
+        let deal_box = deal_box.clone();
+        let app_ng = app.clone();
+        let app_ngn = app.clone();
+        let app_undo = app.clone();
+        let app_redo = app.clone();
+        let app_hint = app.clone();
+        let app_save = app.clone();
+        let app_load = app.clone();
+        let app_win = app.clone();
+        let app_stats = app.clone();
+        let app_abt = app.clone();
+        let app_rls = app.clone();
+        let app_quit = app.clone();
         
         let new_game = Action::new("New Game");
         new_game.on_click(move |_action: &Action, _point: Point| {
-            let mut board = board.borrow_mut();
-            *board = board::Board::new();
-            graphics::render(&mut *canvas.image.borrow_mut(), &board);
-            *last_ng.borrow_mut() = None;
+            app_ng.deal();
         });
         menu.add(&new_game);
 
+        let new_game_num = Action::new("New Game by Number...");
+        new_game_num.on_click(move |_action: &Action, _point: Point| {
+            new_game_by_number(&app_ngn, &deal_box);
+        });
+        menu.add(&new_game_num);
+
+        let undo = Action::new("Undo");
+        undo.on_click(move |_action: &Action, _point: Point| {
+            app_undo.undo();
+        });
+        menu.add(&undo);
+
+        let redo = Action::new("Redo");
+        redo.on_click(move |_action: &Action, _point: Point| {
+            app_redo.redo();
+        });
+        menu.add(&redo);
+
+        let hint = Action::new("Hint");
+        hint.on_click(move |_action: &Action, _point: Point| {
+            app_hint.hint();
+        });
+        menu.add(&hint);
+
+        let save = Action::new("Save");
+        save.on_click(move |_action: &Action, _point: Point| {
+            app_save.save();
+        });
+        menu.add(&save);
+
+        let load = Action::new("Load");
+        load.on_click(move |_action: &Action, _point: Point| {
+            app_load.load();
+        });
+        menu.add(&load);
+
+        let winnable = Action::new(winnable_label(app.winnable_only()));
+        winnable.on_click(move |action: &Action, _point: Point| {
+            app_win.set_winnable_only(!app_win.winnable_only());
+            action.text(winnable_label(app_win.winnable_only()));
+        });
+        menu.add(&winnable);
+
+        let statistics = Action::new("Statistics");
+        statistics.on_click(move |_action: &Action, _point: Point| {
+            stats_box.text(app_stats.stats_text());
+            stats_box.visible.set(true);
+            *app_stats.last.borrow_mut() = None;
+        });
+        menu.add(&statistics);
+
         let about = Action::new("About");
         about.on_click(move |_action: &Action, _point: Point| {
             about_box.visible.set(true);
-            *last_abt.borrow_mut() = None;
+            *app_abt.last.borrow_mut() = None;
         });
         menu.add(&about);
 
         let rules = Action::new("Rules");
         rules.on_click(move |_action: &Action, _point: Point| {
             rules_box.visible.set(true);
-            *last_rls.borrow_mut() = None;
+            *app_rls.last.borrow_mut() = None;
         });
         menu.add(&rules);
 
         let quit = Action::new("Quit");
         quit.on_click(move |_action: &Action, _point: Point| {
+            app_quit.save();
             std::process::exit(0);
         });
         menu.add(&quit);
     }
 
+    //Enter in the deal box starts that deal as well
+    {
+        let app = app.clone();
+        deal_box.on_enter(move |deal_box: &TextBox| {
+            new_game_by_number(&app, deal_box);
+        });
+    }
+
+    //Keyboard shortcuts
+    let key_listener = KeyListener::new();
+    {
+        let app = app.clone();
+        let deal_box = deal_box.clone();
+        key_listener.on_key(move |key: Key| {
+            //While a deal number is being typed, digits and Enter are the box's
+            let typing = !deal_box.text.get().is_empty();
+            match key {
+                Key::Ctrl('z') => app.undo(),
+                Key::Ctrl('y') => app.redo(),
+                Key::F2 => app.deal(),
+                Key::Esc => app.cancel(),
+                Key::Char(c @ '1'..='8') if !typing =>
+                    app.select_stack(Stack::from_col(c as usize - '1' as usize)),
+                Key::Char('q') | Key::Char('Q') => app.select_utility(Utility::Utility0),
+                Key::Char('w') | Key::Char('W') => app.select_utility(Utility::Utility1),
+                Key::Char('e') | Key::Char('E') => app.select_utility(Utility::Utility2),
+                Key::Char('r') | Key::Char('R') => app.collapse(Suit::Red),
+                Key::Char('g') | Key::Char('G') => app.collapse(Suit::Green),
+                Key::Char('b') | Key::Char('B') => app.collapse(Suit::Black),
+                Key::Enter if !typing => app.select_foundation(),
+                Key::Up => app.select_deeper(true),
+                Key::Down => app.select_deeper(false),
+                _ => (),
+            }
+        });
+    }
+
     window.add(&bg);
     window.add(&canvas);
+    window.add(&pointer_listener);
     window.add(&menu);
+    window.add(&deal_box);
+    window.add(&status);
+    window.add(&key_listener);
     window.add(&rules_box);
     window.add(&about_box);
-    window.exec();
+    window.add(&stats_box);
+    window.add(&end_box);
+
+    //Drive the event loop by hand so the title can follow the current deal,
+    //and the timer and animations keep running between events. The pause
+    //keeps it to about 60 frames a second.
+    let mut shown_seed = app.game.borrow().board().seed;
+    let mut shown_size = (0, 0);
+    while window.running.get() {
+        let size = (window.width(), window.height());
+        if size != shown_size {
+            let (width, height) = canvas_size(size.0, size.1);
+            app.resize(width, height);
+            pointer_listener.size(width, height);
+            status.position(10, 16 + height as i32 + 4).size(width, 16);
+            if let Ok(image) = bg_image.resize(size.0, size.1, ResizeType::Lanczos3) {
+                *bg.image.borrow_mut() = image;
+            }
+            bg.size(size.0, size.1);
+            shown_size = size;
+        }
+        window.step();
+        app.tick(about_box.visible.get() || rules_box.visible.get() ||
+                 stats_box.visible.get());
+        let seed = app.game.borrow().board().seed;
+        if seed != shown_seed {
+            window.set_title(&title(seed));
+            shown_seed = seed;
+        }
+        thread::sleep(Duration::from_millis(16));
+    }
+    app.save();
 }
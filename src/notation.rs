@@ -0,0 +1,408 @@
+//! A compact, human-readable notation for cards and positions, meant for
+//! pasting into bug reports and tests.
+//!
+//! Numbered cards are a suit letter and the face value shown on the card,
+//! e.g. `R3` or `G9`. `JR`, `JG` and `JB` are jacks, `JO` is the joker, and
+//! `XR`, `XG` and `XB` are collapsed jack bundles. A position lists the
+//! deal number (if known), the rose, the three utility slots, the top card
+//! of each ordered stack, and each stack from bottom to top, with `-` for
+//! an empty slot:
+//!
+//! ```text
+//! deal: 42
+//! rose: JO
+//! utility: R3 - XB
+//! foundation: R2 - B1
+//! stack 1: G8 JB R7
+//! stack 2:
+//! ...
+//! stack 8: B4
+//! ```
+//!
+//! Moves name their source and destination: `1`-`8` are the stacks, `u1`-`u3`
+//! the utility slots and `f1`-`f3` the ordered stacks. Moving more than one
+//! card off a stack gives the number of cards after a colon, and collapsing
+//! jacks is `X` and the suit:
+//!
+//! ```text
+//! 3-5  3:2-5  3-u1  3-f2  u1-5  u1-f2  XR
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use ::board::{Board, Card, CardOrJacks, Jacks, NumCard, Suit};
+use ::error::Error;
+use ::gamemove::{Move, Stack, Utility, Ordered};
+use ::error::Error::*;
+
+fn suit_letter(s: Suit) -> char {
+    match s {
+        Suit::Red => 'R',
+        Suit::Green => 'G',
+        Suit::Black => 'B',
+    }
+}
+
+fn parse_suit(c: char) -> Option<Suit> {
+    match c {
+        'R' => Some(Suit::Red),
+        'G' => Some(Suit::Green),
+        'B' => Some(Suit::Black),
+        _ => None,
+    }
+}
+
+fn num_card(s: Suit, value: usize) -> NumCard {
+    match s {
+        Suit::Red => NumCard::Red(value),
+        Suit::Green => NumCard::Green(value),
+        Suit::Black => NumCard::Black(value),
+    }
+}
+
+impl fmt::Display for NumCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", suit_letter(self.suit()), self.value() + 1)
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Card::JRed => write!(f, "JR"),
+            Card::JGreen => write!(f, "JG"),
+            Card::JBlack => write!(f, "JB"),
+            Card::Joker => write!(f, "JO"),
+            Card::Num(ref c) => c.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for CardOrJacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CardOrJacks::Card(ref c) => c.fmt(f),
+            CardOrJacks::Jacks(ref j) => write!(f, "X{}", suit_letter(j.suit())),
+        }
+    }
+}
+
+impl FromStr for NumCard {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut chars = s.chars();
+        let suit = chars.next().and_then(parse_suit);
+        let value = chars.as_str().parse::<usize>().ok();
+        match (suit, value) {
+            (Some(suit), Some(value)) if (1..=9).contains(&value) => Ok(num_card(suit, value - 1)),
+            _ => Err(BadCard(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "JR" => Card::JRed,
+            "JG" => Card::JGreen,
+            "JB" => Card::JBlack,
+            "JO" => Card::Joker,
+            _ => Card::Num(s.parse()?),
+        })
+    }
+}
+
+impl FromStr for CardOrJacks {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "XR" => CardOrJacks::Jacks(Jacks::Red),
+            "XG" => CardOrJacks::Jacks(Jacks::Green),
+            "XB" => CardOrJacks::Jacks(Jacks::Black),
+            _ => CardOrJacks::Card(s.parse()?),
+        })
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::StackToStack { src, depth: 1, dst } =>
+                write!(f, "{}-{}", src as usize + 1, dst as usize + 1),
+            Move::StackToStack { src, depth, dst } =>
+                write!(f, "{}:{}-{}", src as usize + 1, depth, dst as usize + 1),
+            Move::StackToUtility { src, dst } =>
+                write!(f, "{}-u{}", src as usize + 1, dst as usize + 1),
+            Move::StackToFoundation { src, dst } =>
+                write!(f, "{}-f{}", src as usize + 1, dst as usize + 1),
+            Move::UtilityToStack { src, dst } =>
+                write!(f, "u{}-{}", src as usize + 1, dst as usize + 1),
+            Move::UtilityToFoundation { src, dst } =>
+                write!(f, "u{}-f{}", src as usize + 1, dst as usize + 1),
+            Move::CollapseJacks(s) => write!(f, "X{}", suit_letter(s)),
+        }
+    }
+}
+
+//One end of a move in move notation
+enum Place {
+    Stack(Stack),
+    Utility(Utility),
+    Ordered(Ordered),
+}
+
+//Parses a 1-based slot number no larger than `max`
+fn parse_slot(s: &str, max: usize) -> Option<usize> {
+    s.parse::<usize>().ok().and_then(|n| if (1..=max).contains(&n) { Some(n - 1) } else { None })
+}
+
+fn parse_place(s: &str) -> Option<Place> {
+    if let Some(n) = s.strip_prefix('u') {
+        parse_slot(n, 3).map(|n| Place::Utility(Utility::from_idx(n)))
+    } else if let Some(n) = s.strip_prefix('f') {
+        parse_slot(n, 3).map(|n| Place::Ordered(Ordered::from_idx(n)))
+    } else {
+        parse_slot(s, 8).map(|n| Place::Stack(Stack::from_col(n)))
+    }
+}
+
+impl FromStr for Move {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let bad = || BadMove(s.to_string());
+        if s.len() == 2 && s.starts_with('X') {
+            return s[1..].chars().next()
+                .and_then(parse_suit)
+                .map(Move::CollapseJacks)
+                .ok_or_else(bad);
+        }
+
+        let dash = s.find('-').ok_or_else(bad)?;
+        let (src, dst) = (&s[..dash], &s[dash + 1..]);
+        let (src, depth) = match src.find(':') {
+            Some(colon) => (&src[..colon],
+                            Some(src[colon + 1..].parse::<usize>().map_err(|_| bad())?)),
+            None => (src, None),
+        };
+        let src = parse_place(src).ok_or_else(bad)?;
+        let dst = parse_place(dst).ok_or_else(bad)?;
+        Ok(match (src, depth, dst) {
+            (Place::Stack(src), depth, Place::Stack(dst)) =>
+                Move::StackToStack { src, depth: depth.unwrap_or(1), dst },
+            (Place::Stack(src), None, Place::Utility(dst)) =>
+                Move::StackToUtility { src, dst },
+            (Place::Stack(src), None, Place::Ordered(dst)) =>
+                Move::StackToFoundation { src, dst },
+            (Place::Utility(src), None, Place::Stack(dst)) =>
+                Move::UtilityToStack { src, dst },
+            (Place::Utility(src), None, Place::Ordered(dst)) =>
+                Move::UtilityToFoundation { src, dst },
+            _ => return Err(bad()),
+        })
+    }
+}
+
+//Writes a slot, using `-` when it is empty
+fn write_slot<T: fmt::Display>(f: &mut fmt::Formatter, slot: Option<&T>) -> fmt::Result {
+    match slot {
+        Some(c) => write!(f, " {}", c),
+        None => write!(f, " -"),
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(seed) = self.seed {
+            writeln!(f, "deal: {}", seed)?;
+        }
+        write!(f, "rose:")?;
+        write_slot(f, self.joker.as_ref())?;
+        write!(f, "\nutility:")?;
+        for slot in &self.utility {
+            write_slot(f, slot.as_ref())?;
+        }
+        write!(f, "\nfoundation:")?;
+        for ordered in &self.ordered {
+            write_slot(f, ordered.last())?;
+        }
+        for (idx, stack) in self.in_play.iter().enumerate() {
+            write!(f, "\nstack {}:", idx + 1)?;
+            for card in stack {
+                write!(f, " {}", card)?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+//Parses a row of exactly three slots, where `-` is an empty slot
+fn parse_slots<T: FromStr<Err = Error>>(line: &str, values: &str) -> Result<[Option<T>; 3], Error> {
+    let mut slots = values.split_whitespace()
+        .map(|v| if v == "-" { Ok(None) } else { v.parse().map(Some) })
+        .collect::<Result<Vec<_>, _>>()?;
+    if slots.len() != 3 { return Err(BadLine(line.to_string())) }
+    let third = slots.pop().unwrap();
+    let second = slots.pop().unwrap();
+    let first = slots.pop().unwrap();
+    Ok([first, second, third])
+}
+
+impl FromStr for Board {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut board = Board {
+            seed: None,
+            joker: None,
+            utility: [None, None, None],
+            ordered: [Vec::new(), Vec::new(), Vec::new()],
+            in_play: [
+                Vec::new(), Vec::new(), Vec::new(), Vec::new(),
+                Vec::new(), Vec::new(), Vec::new(), Vec::new(),
+            ],
+        };
+        let mut seen = Vec::new();
+
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let colon = line.find(':').ok_or_else(|| BadLine(line.to_string()))?;
+            let (key, values) = (line[..colon].trim(), line[colon + 1..].trim());
+            if seen.contains(&key) { return Err(BadLine(line.to_string())) }
+            seen.push(key);
+
+            match key {
+                "deal" => board.seed = Some(values.parse()
+                                            .map_err(|_| BadLine(line.to_string()))?),
+                "rose" => board.joker = match values {
+                    "-" => None,
+                    "JO" => Some(Card::Joker),
+                    _ => return Err(BadLine(line.to_string())),
+                },
+                "utility" => board.utility = parse_slots(line, values)?,
+                "foundation" => {
+                    let tops: [Option<NumCard>; 3] = parse_slots(line, values)?;
+                    for (ordered, top) in board.ordered.iter_mut().zip(tops.iter()) {
+                        if let Some(ref top) = *top {
+                            *ordered = (0..top.value() + 1)
+                                .map(|v| num_card(top.suit(), v))
+                                .collect();
+                        }
+                    }
+                },
+                _ if key.starts_with("stack ") => {
+                    let idx = key["stack ".len()..].parse::<usize>().ok()
+                        .and_then(|n| if (1..=8).contains(&n) { Some(n - 1) } else { None })
+                        .ok_or_else(|| BadLine(line.to_string()))?;
+                    board.in_play[idx] = values.split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()?;
+                },
+                _ => return Err(BadLine(line.to_string())),
+            }
+        }
+
+        validate_cards(&board)?;
+        Ok(board)
+    }
+}
+
+//Ensures the position holds each card of the deck exactly once
+pub(crate) fn validate_cards(b: &Board) -> Result<(), Error> {
+    let mut cards: Vec<Card> = Vec::new();
+    let mut bundles = Vec::new();
+    cards.extend(b.joker.iter().cloned());
+    for slot in b.utility.iter().filter_map(Option::as_ref) {
+        match *slot {
+            CardOrJacks::Card(ref c) => cards.push(c.clone()),
+            CardOrJacks::Jacks(ref j) => bundles.push(j.suit()),
+        }
+    }
+    cards.extend(b.ordered.iter().flat_map(|o| o.iter().cloned().map(Card::Num)));
+    cards.extend(b.in_play.iter().flat_map(|s| s.iter().cloned()));
+
+    let count = cards.len() + bundles.len() * 4;
+    if count != 40 { return Err(WrongCardCount(count)) }
+
+    for (i, card) in cards.iter().enumerate() {
+        let copies = cards.iter().filter(|c| *c == card).count();
+        let allowed = if card.is_jack() { 4 } else { 1 };
+        if copies > allowed && cards[..i].iter().all(|c| c != card) {
+            return Err(DuplicateCard(card.to_string()));
+        }
+        if card.is_jack() && bundles.iter().any(|s| card.suit() == Some(*s)) {
+            return Err(DuplicateCard(card.to_string()));
+        }
+    }
+    for (i, s) in bundles.iter().enumerate() {
+        if bundles[..i].contains(s) {
+            return Err(DuplicateCard(format!("X{}", suit_letter(*s))));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A legal position holding every card exactly once
+    const POSITION: &str = "rose: JO
+utility: XR XG XB
+foundation: R3 G3 B3
+stack 1: R4 G8 R9
+stack 2: G4 R8 G9
+stack 3: B4 B8 B9
+stack 4: R6 R7
+stack 5: G6 G7
+stack 6: B6 B7
+stack 7: B5 R5
+stack 8: G5
+";
+
+    #[test]
+    fn deals_round_trip() {
+        for seed in 0..20 {
+            let board = Board::from_seed(seed);
+            assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        }
+    }
+
+    #[test]
+    fn position_round_trips() {
+        assert_eq!(POSITION.parse::<Board>().unwrap().to_string(), POSITION);
+    }
+
+    #[test]
+    fn rejects_duplicate_card() {
+        let s = POSITION.replace("stack 8: G5", "stack 8: R5");
+        assert!(matches!(s.parse::<Board>(), Err(DuplicateCard(ref c)) if c == "R5"));
+    }
+
+    #[test]
+    fn rejects_missing_card() {
+        let s = POSITION.replace("stack 8: G5", "stack 8:");
+        assert!(matches!(s.parse::<Board>(), Err(WrongCardCount(39))));
+    }
+
+    #[test]
+    fn rejects_loose_jack_with_its_bundle() {
+        let s = POSITION.replace("stack 8: G5", "stack 8: JB");
+        assert!(matches!(s.parse::<Board>(), Err(DuplicateCard(ref c)) if c == "JB"));
+    }
+
+    #[test]
+    fn rejects_repeated_stack() {
+        let s = POSITION.replace("stack 3: B4 B8 B9\n", "stack 3: B4 B8 B9\nstack 3: B4 B8 B9\n");
+        assert!(matches!(s.parse::<Board>(), Err(BadLine(_))));
+    }
+
+    #[test]
+    fn rejects_stack_9() {
+        let s = POSITION.replace("stack 8:", "stack 9:");
+        assert!(matches!(s.parse::<Board>(), Err(BadLine(ref l)) if l == "stack 9: G5"));
+    }
+}
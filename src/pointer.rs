@@ -0,0 +1,81 @@
+//! An invisible widget laid over the canvas that reports the left mouse
+//! button going down, moving and coming back up, so cards can be dragged
+//! as well as clicked.
+use orbtk::{Event, Point, Rect, Renderer, Widget};
+use orbtk::traits::Place;
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+
+/// Points are relative to the widget's top left corner.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Pointer {
+    Press(Point),
+    Move(Point),
+    Release(Point),
+}
+
+type Handler = Box<dyn Fn(Pointer)>;
+
+pub struct PointerListener {
+    rect: Cell<Rect>,
+    //Where the button was last seen held down, while it is held
+    held: Cell<Option<Point>>,
+    on_pointer: RefCell<Option<Handler>>,
+}
+
+impl PointerListener {
+    //Like every orbtk widget it lives in an Arc, though it stays on one thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn new() -> Arc<Self> {
+        Arc::new(PointerListener {
+            rect: Cell::new(Rect::new(0, 0, 0, 0)),
+            held: Cell::new(None),
+            on_pointer: RefCell::new(None),
+        })
+    }
+
+    pub fn on_pointer<F: Fn(Pointer) + 'static>(&self, func: F) -> &Self {
+        *self.on_pointer.borrow_mut() = Some(Box::new(func));
+        self
+    }
+
+    fn emit(&self, pointer: Pointer) {
+        if let Some(ref func) = *self.on_pointer.borrow() {
+            func(pointer);
+        }
+    }
+}
+
+impl Place for PointerListener {}
+
+impl Widget for PointerListener {
+    fn rect(&self) -> &Cell<Rect> {
+        &self.rect
+    }
+
+    fn name(&self) -> &str {
+        "PointerListener"
+    }
+
+    fn draw(&self, _renderer: &mut dyn Renderer, _focused: bool) {}
+
+    fn event(&self, event: Event, _focused: bool, redraw: &mut bool) -> bool {
+        if let Event::Mouse { point, left_button, .. } = event {
+            let rect = self.rect.get();
+            let local = Point::new(point.x - rect.x, point.y - rect.y);
+            let inside = local.x >= 0 && local.y >= 0 &&
+                local.x < rect.width as i32 && local.y < rect.height as i32;
+            let pointer = match (self.held.get(), left_button) {
+                (None, true) if inside => Pointer::Press(local),
+                (Some(last), true) if last != local => Pointer::Move(local),
+                (Some(_), false) => Pointer::Release(local),
+                _ => return false,
+            };
+            self.held.set(if left_button { Some(local) } else { None });
+            self.emit(pointer);
+            *redraw = true;
+        }
+        false
+    }
+}
@@ -0,0 +1,160 @@
+//! Game records: a starting position followed by the moves played from it,
+//! in move notation. A game dealt from a deal number is recorded by that
+//! number alone; anything else records the full starting position.
+//!
+//! ```text
+//! cvsolitaire record 1
+//! deal: 42
+//! moves: 3-5 3:2-5 XR u1-f2
+//! ```
+//!
+//! ```text
+//! cvsolitaire record 1
+//! position:
+//! rose: -
+//! utility: - - -
+//! ...
+//! moves: 3-5 3:2-5 XR u1-f2
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use ::board::Board;
+use ::error::Error;
+use ::error::Error::*;
+use ::game::Game;
+use ::gamemove::Move;
+
+const HEADER: &str = "cvsolitaire record 1";
+
+#[derive(Clone)]
+pub struct Record {
+    pub start: Board,
+    pub moves: Vec<Move>,
+}
+
+impl Record {
+    /// Records the moves played so far in `game`, back to its first move.
+    pub fn from_game(game: &Game) -> Self {
+        Record {
+            start: game.history().first()
+                .map(|turn| turn.before.clone())
+                .unwrap_or_else(|| game.board().clone()),
+            moves: game.history().iter().map(|turn| turn.mv.clone()).collect(),
+        }
+    }
+
+    /// Plays the recorded moves from the starting position.
+    pub fn replay(&self) -> Result<Game, Error> {
+        let mut game = Game::new(self.start.clone());
+        for m in &self.moves {
+            game.play(m.clone())?;
+        }
+        Ok(game)
+    }
+
+    //A start that is exactly its own deal can be recorded by the deal number
+    fn dealt_seed(&self) -> Option<u64> {
+        self.start.seed.filter(|seed| Board::from_seed(*seed) == self.start)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        match self.dealt_seed() {
+            Some(seed) => writeln!(f, "deal: {}", seed)?,
+            None => write!(f, "position:\n{}", self.start)?,
+        }
+        write!(f, "moves:")?;
+        for m in &self.moves {
+            write!(f, " {}", m)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl FromStr for Record {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        if lines.next() != Some(HEADER) { return Err(BadRecord("missing header".to_string())) }
+
+        let start = match lines.next() {
+            Some(line) if line.starts_with("deal:") => {
+                let seed = line["deal:".len()..].trim().parse()
+                    .map_err(|_| BadRecord(line.to_string()))?;
+                Board::from_seed(seed)
+            },
+            Some("position:") => lines
+                .take_while(|l| !l.starts_with("moves:"))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .parse()?,
+            _ => return Err(BadRecord("missing deal or position".to_string())),
+        };
+        Ok(Record { start, moves: parse_moves(s)? })
+    }
+}
+
+//The moves follow `moves:` and may continue over several lines
+fn parse_moves(s: &str) -> Result<Vec<Move>, Error> {
+    let idx = s.find("moves:").ok_or_else(|| BadRecord("missing moves".to_string()))?;
+    s[idx + "moves:".len()..].split_whitespace().map(str::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::solver::{self, Solution};
+
+    //The winning line for deal 1, which the solver settles quickly
+    fn winning_line() -> Vec<Move> {
+        match solver::solve(&Board::from_seed(1), solver::DEFAULT_NODE_BUDGET) {
+            Solution::Solved(moves) => moves,
+            other => panic!("deal 1 should be solved, got {:?}", other),
+        }
+    }
+
+    fn round_trip(record: &Record) -> Record {
+        record.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn deal_round_trips() {
+        let record = Record { start: Board::from_seed(1), moves: winning_line() };
+        let text = record.to_string();
+        assert!(text.contains("\ndeal: 1\n"));
+        let parsed = round_trip(&record);
+        assert_eq!(parsed.start, record.start);
+        assert_eq!(parsed.moves, record.moves);
+        assert!(parsed.replay().unwrap().board().is_won());
+    }
+
+    #[test]
+    fn position_round_trips() {
+        let line = winning_line();
+        let mut game = Game::new(Board::from_seed(1));
+        for m in &line[..5] {
+            game.play(m.clone()).unwrap();
+        }
+        let record = Record { start: game.board().clone(), moves: line[5..].to_vec() };
+        assert!(record.to_string().contains("\nposition:\n"));
+        let parsed = round_trip(&record);
+        assert_eq!(parsed.start, record.start);
+        assert_eq!(parsed.moves, record.moves);
+    }
+
+    #[test]
+    fn rejects_bad_move_token() {
+        assert!(matches!(parse_moves("moves: 3-5 9-1"), Err(BadMove(ref m)) if m == "9-1"));
+    }
+
+    #[test]
+    fn replay_fails_on_illegal_move() {
+        //Nothing is in the utility slots at the deal
+        let record: Record = "cvsolitaire record 1\ndeal: 1\nmoves: u1-3\n".parse().unwrap();
+        assert!(record.replay().is_err());
+    }
+}
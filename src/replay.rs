@@ -0,0 +1,75 @@
+//! Steps through a recorded game one change at a time. Each player move is
+//! one step and each card the automatic sweep moves afterwards is another.
+use ::board::{Board, Card};
+use ::error::Error;
+use ::gamemove::Move;
+use ::record::Record;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Step {
+    Start,
+    Move(Move),
+    Sweep(Card),
+}
+
+pub struct Replay {
+    //Each step paired with the board right after it
+    frames: Vec<(Step, Board)>,
+    pos: usize,
+}
+
+impl Replay {
+    /// Expands a record into steps, failing if any recorded move is illegal.
+    pub fn new(record: &Record) -> Result<Self, Error> {
+        let mut board = record.start.clone();
+        let mut frames = vec![(Step::Start, board.clone())];
+        for m in &record.moves {
+            let v = board.get_valid(m.clone())?;
+            board.make_move(v);
+            frames.push((Step::Move(m.clone()), board.clone()));
+            while let Some(card) = board.sweep_one() {
+                frames.push((Step::Sweep(card), board.clone()));
+            }
+        }
+        Ok(Replay { frames, pos: 0 })
+    }
+
+    /// The number of steps, counting the starting position.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Always false, since there is at least the starting position.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn step(&self) -> &Step {
+        &self.frames[self.pos].0
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.frames[self.pos].1
+    }
+
+    /// Moves to step `pos`, clamped to the last step.
+    pub fn seek(&mut self, pos: usize) {
+        self.pos = ::std::cmp::min(pos, self.frames.len() - 1);
+    }
+
+    pub fn forward(&mut self) -> bool {
+        if self.pos + 1 >= self.frames.len() { return false }
+        self.pos += 1;
+        true
+    }
+
+    pub fn back(&mut self) -> bool {
+        if self.pos == 0 { return false }
+        self.pos -= 1;
+        true
+    }
+}
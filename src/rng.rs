@@ -0,0 +1,56 @@
+//! A small deterministic PRNG for dealing. The algorithm (SplitMix64 with a
+//! Fisher-Yates shuffle) is fixed so that a deal number always produces the
+//! same layout, regardless of the version of `rand` in use.
+
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    //Uniform in 0..n, rejecting the biased tail of the range
+    fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone { return x % n }
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            values.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitMix64;
+
+    //Changing any of these changes every numbered deal
+    #[test]
+    fn splitmix64_reference_outputs() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+        assert_eq!(rng.next_u64(), 0x06C4_5D18_8009_454F);
+    }
+
+    #[test]
+    fn shuffle_is_fixed_by_seed() {
+        let mut values: Vec<u32> = (0..10).collect();
+        SplitMix64::new(42).shuffle(&mut values);
+        assert_eq!(values, vec![0, 9, 5, 8, 6, 4, 7, 2, 1, 3]);
+    }
+}
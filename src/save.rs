@@ -0,0 +1,170 @@
+//! Saving games to disk. A save file is JSON holding a format version, the
+//! game, including its undo/redo history, and how long it has been played.
+//! The version is bumped whenever the layout changes, and files from other
+//! versions are rejected rather than misread.
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ::serde::Serialize;
+use ::serde::de::DeserializeOwned;
+
+use ::game::Game;
+
+pub const VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(::serde_json::Error),
+    UnsupportedVersion(u32),
+    /// The file parsed, but the game in it isn't one that could have been played
+    Invalid(::error::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<::serde_json::Error> for Error {
+    fn from(e: ::serde_json::Error) -> Self {
+        Error::Parse(e)
+    }
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    game: &'a Game,
+    elapsed_secs: u64,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    version: u32,
+    game: ::serde_json::Value,
+    elapsed_secs: u64,
+}
+
+/// Where the game keeps its files: `$HOME/.cvsolitaire`.
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".cvsolitaire"))
+}
+
+/// The file the frontend saves to on quit and restores from on launch.
+pub fn default_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("save.json"))
+}
+
+/// The save file for `game`, which has been played for `elapsed`.
+pub fn to_string(game: &Game, elapsed: Duration) -> String {
+    ::serde_json::to_string_pretty(&save_file(game, elapsed))
+        .expect("Games always serialize")
+}
+
+/// The game in a save file and how long it had been played.
+pub fn from_str(s: &str) -> Result<(Game, Duration), Error> {
+    from_file(::serde_json::from_str(s)?)
+}
+
+//Checks the version before trying to make sense of the rest, then checks
+//the game itself so a damaged file can't panic undo or redo later
+fn from_file(file: SaveFile) -> Result<(Game, Duration), Error> {
+    if file.version != VERSION { return Err(Error::UnsupportedVersion(file.version)) }
+    let game: Game = ::serde_json::from_value(file.game)?;
+    game.validate().map_err(Error::Invalid)?;
+    Ok((game, Duration::from_secs(file.elapsed_secs)))
+}
+
+fn save_file<'a>(game: &'a Game, elapsed: Duration) -> SaveFileRef<'a> {
+    SaveFileRef { version: VERSION, game, elapsed_secs: elapsed.as_secs() }
+}
+
+pub fn save(path: &Path, game: &Game, elapsed: Duration) -> Result<(), Error> {
+    write_json(path, &save_file(game, elapsed))
+}
+
+pub fn load(path: &Path) -> Result<(Game, Duration), Error> {
+    from_file(read_json(path)?)
+}
+
+/// Writes `value` to `path` as JSON, creating the directory if need be.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(::serde_json::to_string_pretty(value)?.as_bytes())?;
+    Ok(())
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let mut s = String::new();
+    File::open(path)?.read_to_string(&mut s)?;
+    Ok(::serde_json::from_str(&s)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::board::Board;
+    use ::solver::{self, Solution};
+
+    //Deal 1 a few moves in, with one move undone so both histories are saved
+    fn game() -> Game {
+        let board = Board::from_seed(1);
+        let line = match solver::solve(&board, solver::DEFAULT_NODE_BUDGET) {
+            Solution::Solved(moves) => moves,
+            other => panic!("deal 1 should be solved, got {:?}", other),
+        };
+        let mut game = Game::new(board);
+        for m in line.into_iter().take(6) {
+            game.play(m).unwrap();
+        }
+        game.undo();
+        game
+    }
+
+    //The save file for `game`, changed by `f` before being written out
+    fn edited<F: FnOnce(&mut ::serde_json::Value)>(game: &Game, f: F) -> String {
+        let mut file = ::serde_json::to_value(save_file(game, Duration::from_secs(0))).unwrap();
+        f(&mut file);
+        file.to_string()
+    }
+
+    #[test]
+    fn round_trips() {
+        let game = game();
+        let (loaded, elapsed) = from_str(&to_string(&game, Duration::from_secs(75))).unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.history().len(), game.history().len());
+        assert!(loaded.can_redo());
+        assert_eq!(elapsed, Duration::from_secs(75));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let s = edited(&game(), |file| file["version"] = (VERSION - 1).into());
+        assert!(matches!(from_str(&s), Err(Error::UnsupportedVersion(v)) if v == VERSION - 1));
+    }
+
+    #[test]
+    fn rejects_history_that_doesnt_lead_to_the_board() {
+        let s = edited(&game(), |file| {
+            file["game"]["board"] = ::serde_json::to_value(Board::from_seed(2)).unwrap();
+        });
+        assert!(matches!(from_str(&s), Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_missing_cards() {
+        let s = edited(&Game::new(Board::from_seed(1)), |file| {
+            file["game"]["board"]["in_play"][0] = ::serde_json::Value::Array(Vec::new());
+        });
+        assert!(matches!(from_str(&s), Err(Error::Invalid(_))));
+    }
+}
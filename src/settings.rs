@@ -0,0 +1,25 @@
+//! Preferences that carry over between sessions, kept as JSON next to the
+//! save file. Fields missing from the file take their default.
+use std::path::{Path, PathBuf};
+
+use ::save::{self, Error};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Deal only games the solver has proven winnable
+    pub winnable_only: bool,
+}
+
+/// The file the frontend keeps its settings in.
+pub fn default_path() -> Option<PathBuf> {
+    save::data_dir().map(|dir| dir.join("settings.json"))
+}
+
+pub fn save(path: &Path, settings: &Settings) -> Result<(), Error> {
+    save::write_json(path, settings)
+}
+
+pub fn load(path: &Path) -> Result<Settings, Error> {
+    save::read_json(path)
+}
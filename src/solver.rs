@@ -0,0 +1,243 @@
+//! Searches for a winning line from a position. The search is a depth-first
+//! search over moves as the player would make them, with the automatic
+//! `sweep_free` step applied after each one. Positions that only differ by
+//! the order of the stacks or utility slots are treated as the same
+//! position, and each position is expanded at most once. Hints come from a
+//! separate best-first search that looks for short lines instead.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use ::board::{Board, Card, CardOrJacks, NumCard};
+use ::gamemove::Move;
+
+/// A node budget large enough to settle the vast majority of deals.
+pub const DEFAULT_NODE_BUDGET: usize = 100_000;
+
+/// A smaller budget for picking winnable deals, which happens while the
+/// player waits. Deals that need more than this are passed over, so it only
+/// trims the pool of deals, never lets an unwinnable one through.
+pub const DEAL_NODE_BUDGET: usize = 20_000;
+
+/// A budget for hints, small enough to answer without a noticeable pause.
+/// It finds a line for most fresh deals, and more easily as a game goes on.
+pub const HINT_NODE_BUDGET: usize = 5_000;
+
+//How much a card still in the way counts for against a move already made,
+//when ranking positions for a hint. Higher finds lines faster but longer.
+const REMAINING_WEIGHT: usize = 3;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Solution {
+    /// The moves to play, in order, to win from the given position.
+    Solved(Vec<Move>),
+    /// Every reachable position has been searched without finding a win.
+    Unsolvable,
+    /// The node budget ran out before the search finished.
+    OutOfBudget,
+}
+
+/// Searches `board` for a win, expanding at most `node_budget` positions.
+pub fn solve(board: &Board, node_budget: usize) -> Solution {
+    let mut search = Search {
+        visited: HashSet::new(),
+        nodes: 0,
+        node_budget,
+    };
+    search.visited.insert(key(board));
+    let mut path = Vec::new();
+    match search.dfs(board, &mut path) {
+        Some(true) => Solution::Solved(path),
+        Some(false) => Solution::Unsolvable,
+        None => Solution::OutOfBudget,
+    }
+}
+
+/// The first move of a short winning line from `board`, if one can be found
+/// within the node budget.
+pub fn hint(board: &Board, node_budget: usize) -> Option<Move> {
+    short_line(board, node_budget).and_then(|line| line.into_iter().next())
+}
+
+//A best-first search ranking positions by the moves made so far plus the
+//cards still in the way. Its lines run to a few dozen moves where `solve`
+//can take hundreds, though it gives up sooner on hard positions.
+fn short_line(board: &Board, node_budget: usize) -> Option<Vec<Move>> {
+    let mut found = vec![Found { board: board.clone(), from: None, depth: 0 }];
+    let mut visited = HashSet::new();
+    visited.insert(key(board));
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((remaining(board), 0)));
+
+    let mut nodes = 0;
+    while let Some(Reverse((_, idx))) = open.pop() {
+        if found[idx].board.is_won() { return Some(line_to(&found, idx)) }
+        if nodes >= node_budget { return None }
+        nodes += 1;
+
+        let (b, depth) = (found[idx].board.clone(), found[idx].depth);
+        for m in b.legal_moves() {
+            let child = play(&b, &m);
+            if visited.insert(key(&child)) {
+                open.push(Reverse((depth + 1 + REMAINING_WEIGHT * remaining(&child), found.len())));
+                found.push(Found { board: child, from: Some((idx, m)), depth: depth + 1 });
+            }
+        }
+    }
+    None
+}
+
+//A position reached by the best-first search, with the index of the
+//position and the move it was reached by, and how many moves in it is
+struct Found {
+    board: Board,
+    from: Option<(usize, Move)>,
+    depth: usize,
+}
+
+//The moves leading to position `idx` of a best-first search
+fn line_to(found: &[Found], idx: usize) -> Vec<Move> {
+    let mut line = Vec::new();
+    let mut idx = idx;
+    while let Some((parent, ref m)) = found[idx].from {
+        line.push(m.clone());
+        idx = parent;
+    }
+    line.reverse();
+    line
+}
+
+struct Search {
+    visited: HashSet<Vec<u8>>,
+    nodes: usize,
+    node_budget: usize,
+}
+
+impl Search {
+    //Some(true) if a win was found below `b`, Some(false) if there is none,
+    //and None if the budget ran out first.
+    fn dfs(&mut self, b: &Board, path: &mut Vec<Move>) -> Option<bool> {
+        if b.is_won() { return Some(true) }
+        if self.nodes >= self.node_budget { return None }
+        self.nodes += 1;
+
+        let mut children = Vec::new();
+        for m in b.legal_moves() {
+            let child = play(b, &m);
+            if self.visited.insert(key(&child)) {
+                children.push((remaining(&child), m, child));
+            }
+        }
+        //Try the moves that make the most progress first
+        children.sort_by_key(|&(remaining, _, _)| remaining);
+
+        for (_, m, child) in children {
+            path.push(m);
+            if self.dfs(&child, path)? { return Some(true) }
+            path.pop();
+        }
+        Some(false)
+    }
+}
+
+//Makes a legal move on a copy of the board, including the automatic sweep
+fn play(b: &Board, m: &Move) -> Board {
+    let mut child = b.clone();
+    child.make_move(b.get_valid(m.clone()).unwrap());
+    child.sweep_free();
+    child
+}
+
+//Cards still in the way of a win
+fn remaining(b: &Board) -> usize {
+    b.in_play.iter().map(Vec::len).sum::<usize>() +
+        b.utility.iter().filter(|slot| matches!(**slot, Some(CardOrJacks::Card(_)))).count()
+}
+
+fn card_code(c: &Card) -> u8 {
+    match *c {
+        Card::Num(NumCard::Black(n)) => n as u8,
+        Card::Num(NumCard::Red(n)) => 9 + n as u8,
+        Card::Num(NumCard::Green(n)) => 18 + n as u8,
+        Card::JBlack => 27,
+        Card::JRed => 28,
+        Card::JGreen => 29,
+        Card::Joker => 30,
+    }
+}
+
+//A canonical encoding of the position, ignoring the order of the stacks
+//and utility slots. The ordered stacks are determined by the other cards.
+fn key(b: &Board) -> Vec<u8> {
+    let mut stacks: Vec<Vec<u8>> = b.in_play.iter()
+        .map(|stack| stack.iter().map(card_code).collect())
+        .collect();
+    stacks.sort();
+    let mut utility: Vec<u8> = b.utility.iter()
+        .map(|slot| match *slot {
+            None => 255,
+            Some(CardOrJacks::Card(ref c)) => card_code(c),
+            Some(CardOrJacks::Jacks(_)) => 254,
+        })
+        .collect();
+    utility.sort();
+
+    let mut key = utility;
+    for stack in stacks {
+        key.push(253);
+        key.extend(stack);
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::game::Game;
+
+    #[test]
+    fn solved_line_wins_when_played() {
+        let board = Board::from_seed(1);
+        let moves = match solve(&board, DEFAULT_NODE_BUDGET) {
+            Solution::Solved(moves) => moves,
+            other => panic!("deal 1 should be solved, got {:?}", other),
+        };
+        let mut game = Game::new(board);
+        for m in moves {
+            game.play(m).unwrap();
+        }
+        assert!(game.board().is_won());
+    }
+
+    #[test]
+    fn hint_line_is_short_and_wins() {
+        let line = short_line(&Board::from_seed(1), HINT_NODE_BUDGET).unwrap();
+        assert!(line.len() < 60, "{} moves", line.len());
+        let mut game = Game::new(Board::from_seed(1));
+        for m in line {
+            game.play(m).unwrap();
+        }
+        assert!(game.board().is_won());
+    }
+
+    #[test]
+    fn tiny_budget_runs_out() {
+        assert_eq!(solve(&Board::from_seed(1), 1), Solution::OutOfBudget);
+    }
+
+    //Every 4 is buried and nothing on top can move anywhere
+    #[test]
+    fn dead_position_is_unsolvable() {
+        let board: Board = "rose: JO\n\
+                            utility: XR XG XB\n\
+                            foundation: R3 G3 B3\n\
+                            stack 1: R4 G8 R9\n\
+                            stack 2: G4 R8 G9\n\
+                            stack 3: B4 B8 B9\n\
+                            stack 4: R6 R7\n\
+                            stack 5: G6 G7\n\
+                            stack 6: B6 B7\n\
+                            stack 7: B5 R5\n\
+                            stack 8: G5\n".parse().unwrap();
+        assert_eq!(solve(&board, DEFAULT_NODE_BUDGET), Solution::Unsolvable);
+    }
+}
@@ -0,0 +1,110 @@
+//! Lifetime statistics, kept as JSON next to the save file. Fields missing
+//! from the file read as zero, so older files keep working as fields are
+//! added.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ::save::{self, Error};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub started: u32,
+    pub won: u32,
+    //Wins in a row, up to and including the last finished game
+    pub streak: u32,
+    pub longest_streak: u32,
+    /// Best wins, by deal number
+    pub best: BTreeMap<u64, Best>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Best {
+    pub fastest_secs: u64,
+    pub fewest_moves: usize,
+}
+
+impl Stats {
+    pub fn start(&mut self) {
+        self.started += 1;
+    }
+
+    /// Counts a win, taking `secs` seconds and `moves` moves. Wins of
+    /// numbered deals are also checked against that deal's best.
+    pub fn win(&mut self, seed: Option<u64>, secs: u64, moves: usize) {
+        self.won += 1;
+        self.streak += 1;
+        self.longest_streak = self.longest_streak.max(self.streak);
+        if let Some(seed) = seed {
+            let best = self.best.entry(seed).or_insert(Best {
+                fastest_secs: secs,
+                fewest_moves: moves,
+            });
+            best.fastest_secs = best.fastest_secs.min(secs);
+            best.fewest_moves = best.fewest_moves.min(moves);
+        }
+    }
+
+    /// Counts a game given up on, which ends the streak.
+    pub fn lose(&mut self) {
+        self.streak = 0;
+    }
+
+    /// Share of started games that were won, rounded to a whole percent.
+    pub fn win_percent(&self) -> u32 {
+        if self.started == 0 { return 0 }
+        ((self.won as f64) * 100.0 / (self.started as f64)).round() as u32
+    }
+}
+
+/// The file the frontend keeps statistics in.
+pub fn default_path() -> Option<PathBuf> {
+    save::data_dir().map(|dir| dir.join("stats.json"))
+}
+
+pub fn save(path: &Path, stats: &Stats) -> Result<(), Error> {
+    save::write_json(path, stats)
+}
+
+pub fn load(path: &Path) -> Result<Stats, Error> {
+    save::read_json(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn win_counts_and_keeps_the_best_of_each_deal() {
+        let mut stats = Stats::default();
+        stats.win(Some(7), 300, 80);
+        stats.win(Some(7), 200, 95);
+        stats.win(None, 50, 10);
+        assert_eq!((stats.won, stats.streak, stats.longest_streak), (3, 3, 3));
+        assert_eq!(stats.best.len(), 1);
+        assert_eq!(stats.best[&7], Best { fastest_secs: 200, fewest_moves: 80 });
+    }
+
+    #[test]
+    fn lose_ends_the_streak_but_not_the_longest() {
+        let mut stats = Stats::default();
+        stats.win(None, 60, 40);
+        stats.win(None, 60, 40);
+        stats.lose();
+        stats.win(None, 60, 40);
+        assert_eq!((stats.won, stats.streak, stats.longest_streak), (3, 1, 2));
+    }
+
+    #[test]
+    fn win_percent_rounds_and_handles_no_games() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.win_percent(), 0);
+        for _ in 0..3 {
+            stats.start();
+        }
+        stats.win(None, 60, 40);
+        assert_eq!(stats.win_percent(), 33);
+        stats.win(None, 60, 40);
+        assert_eq!(stats.win_percent(), 67);
+    }
+}
@@ -0,0 +1,146 @@
+//! Replay mode: steps through a game record. The board never takes clicks
+//! here, so nothing goes through `get_valid`.
+use cvsolitaire::click::{Layout, BOARD_SIZE};
+use cvsolitaire::record::Record;
+use cvsolitaire::replay::{Replay, Step};
+use orbtk::{Window, Rect, Image, Color, Point, Menu, Action, Button, Label, ProgressBar};
+use orbtk::traits::{Click, Place, Text};
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use graphics::{self, Surface};
+use keys::{Key, KeyListener};
+
+struct Viewer {
+    replay: RefCell<Replay>,
+    canvas: Arc<Image>,
+    scrub: Arc<ProgressBar>,
+    label: Arc<Label>,
+}
+
+impl Viewer {
+    fn show(&self) {
+        let replay = self.replay.borrow();
+        let last = replay.len() - 1;
+        let image = &mut *self.canvas.image.borrow_mut();
+        graphics::render(&mut Surface::new(image, Layout::default()), replay.board());
+        self.scrub.value.set((replay.pos() * 100 / ::std::cmp::max(last, 1)) as i32);
+        self.label.text(match *replay.step() {
+            Step::Start => format!("Start, {} steps", last),
+            Step::Move(ref m) => format!("Step {}/{}: {}", replay.pos(), last, m),
+            Step::Sweep(ref c) => format!("Step {}/{}: autosweep {}", replay.pos(), last, c),
+        });
+    }
+
+    fn forward(&self) {
+        if self.replay.borrow_mut().forward() { self.show() }
+    }
+
+    fn back(&self) {
+        if self.replay.borrow_mut().back() { self.show() }
+    }
+
+    //Jumps to the step under a click on the scrub bar
+    fn scrub_to(&self, point: Point) {
+        let width = self.scrub.rect.get().width as i32;
+        let last = self.replay.borrow().len() as i32 - 1;
+        let pos = (point.x * last + width / 2) / ::std::cmp::max(width, 1);
+        self.replay.borrow_mut().seek(::std::cmp::max(pos, 0) as usize);
+        self.show();
+    }
+}
+
+fn read_record(path: &str) -> Result<Replay, String> {
+    let mut s = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut s))
+        .map_err(|e| e.to_string())?;
+    let record: Record = s.parse().map_err(|e: ::cvsolitaire::Error| e.to_string())?;
+    Replay::new(&record).map_err(|e| e.to_string())
+}
+
+/// Opens the game record at `path` in replay mode.
+pub fn run(path: &str) {
+    let replay = match read_record(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Couldn't replay {}: {}", path, e);
+            process::exit(1);
+        },
+    };
+
+    let menu = Menu::new("Menu");
+    menu.position(10, 0).size(32, 16);
+
+    let back = Button::new();
+    back.text("<").position(50, 0).size(20, 16);
+    let forward = Button::new();
+    forward.text(">").position(75, 0).size(20, 16);
+    let scrub = ProgressBar::new();
+    scrub.position(100, 0).size(200, 16);
+    let label = Label::new();
+    label.position(310, 0).size(300, 16);
+
+    //The board below the menu bar, with a margin around it
+    let (width, height) = (BOARD_SIZE.0 + 20, BOARD_SIZE.1 + 16 + 10);
+    let mut window = Window::new_flags(Rect::new(100, 100, width, height),
+                                       "Charles Village Solitaire - Replay",
+                                       &[::orbclient::WindowFlag::Async]);
+    let bg =
+        Image::from_image(::orbimage::parse_png(include_bytes!("../assets/bg.png")).unwrap());
+    let canvas = Image::from_color(BOARD_SIZE.0, BOARD_SIZE.1, Color::rgba(255, 255, 255, 0));
+    canvas.position(10, 16);
+
+    let viewer = Rc::new(Viewer {
+        replay: RefCell::new(replay),
+        canvas: canvas.clone(),
+        scrub: scrub.clone(),
+        label: label.clone(),
+    });
+    viewer.show();
+
+    {
+        let viewer = viewer.clone();
+        back.on_click(move |_button: &Button, _point: Point| viewer.back());
+    }
+    {
+        let viewer = viewer.clone();
+        forward.on_click(move |_button: &Button, _point: Point| viewer.forward());
+    }
+    {
+        let viewer = viewer.clone();
+        scrub.on_click(move |_scrub: &ProgressBar, point: Point| viewer.scrub_to(point));
+    }
+
+    let key_listener = KeyListener::new();
+    {
+        let viewer = viewer.clone();
+        key_listener.on_key(move |key: Key| {
+            match key {
+                Key::Left => viewer.back(),
+                Key::Right => viewer.forward(),
+                _ => (),
+            }
+        });
+    }
+
+    let quit = Action::new("Quit");
+    quit.on_click(move |_action: &Action, _point: Point| {
+        process::exit(0);
+    });
+    menu.add(&quit);
+
+    window.add(&bg);
+    window.add(&canvas);
+    window.add(&menu);
+    window.add(&back);
+    window.add(&forward);
+    window.add(&scrub);
+    window.add(&label);
+    window.add(&key_listener);
+    window.exec();
+}